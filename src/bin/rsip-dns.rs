@@ -0,0 +1,396 @@
+//! Resolves a SIP URI the way a SIP stack using `rsip-dns` would, printing the ordered list of
+//! targets along with the DNS queries and answers that led to each one.
+//!
+//! ```text
+//! rsip-dns sips:alice@example.com
+//! rsip-dns --transports udp,tcp --family ipv4 --nameserver 10.0.0.53 sip:example.com
+//! rsip-dns --json sip:example.com;transport=tcp
+//! ```
+//!
+//! With the `lint` subcommand, it inspects the DNS records of a domain instead, reporting any
+//! misconfiguration (see [Linter](rsip_dns::Linter)):
+//!
+//! ```text
+//! rsip-dns lint example.com
+//! ```
+
+use rsip::{Domain, Transport, Uri};
+use rsip_dns::{
+    trust_dns_resolver::{
+        config::{LookupIpStrategy, NameServerConfigGroup, ResolverConfig, ResolverOpts},
+        system_conf, Resolver,
+    },
+    Context, Linter, Lookup, Report, ResolvableExt, Severity, SupportedTransports, Target,
+    TraceEvent, TrustDnsClient,
+};
+use serde_json::{json, Value};
+use std::{convert::TryFrom, net::SocketAddr, process};
+
+const USAGE: &str = "\
+Usage: rsip-dns [OPTIONS] <URI>
+       rsip-dns lint [OPTIONS] <DOMAIN>
+
+Resolves a SIP URI into the ordered list of (ip, port, transport) targets, as RFC 3263 describes.
+With lint, reports misconfigurations of the SIP related DNS records of the domain instead.
+
+Options:
+  -t, --transports <LIST>   comma separated list of supported transports
+                            (udp, tcp, tls, sctp, tls-sctp, ws, wss), all of them by default
+  -f, --family <POLICY>     address family policy of A/AAAA lookups: ipv4, ipv6, both,
+                            ipv4-then-ipv6 (default) or ipv6-then-ipv4
+  -n, --nameserver <ADDR>   ip address (and port, 53 by default) of the name server to use,
+                            instead of the system configuration, can be repeated
+      --json                prints the targets as JSON
+  -h, --help                prints this message
+";
+
+#[derive(Debug)]
+struct Args {
+    command: Command,
+    supported_transports: SupportedTransports,
+    ip_strategy: LookupIpStrategy,
+    nameservers: Vec<SocketAddr>,
+    json: bool,
+}
+
+#[derive(Debug)]
+enum Command {
+    Resolve(Uri),
+    Lint(Domain),
+}
+
+//a yielded target along with the trace events that happened since the previous one
+struct Resolved {
+    target: Target,
+    events: Vec<TraceEvent>,
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let succeeded = match &args.command {
+        Command::Resolve(uri) => resolve(&args, uri).map(|resolved| {
+            match args.json {
+                true => println!("{:#}", to_json(uri, &resolved)),
+                false => print_human(uri, &resolved),
+            }
+            !resolved.is_empty()
+        }),
+        Command::Lint(domain) => lint(&args, domain).map(|report| {
+            match args.json {
+                true => println!("{:#}", report_to_json(&report)),
+                false => print_report(&report),
+            }
+            !report.has_errors()
+        }),
+    };
+
+    match succeeded {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut lint = false;
+    let mut target = None;
+    let mut supported_transports = SupportedTransports::any();
+    let mut ip_strategy = LookupIpStrategy::default();
+    let mut nameservers = vec![];
+    let mut json = false;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("missing value for {}", name));
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--json" => json = true,
+            "-t" | "--transports" => {
+                supported_transports = parse_transports(&value(&arg)?)?;
+            }
+            "-f" | "--family" => ip_strategy = parse_family(&value(&arg)?)?,
+            "-n" | "--nameserver" => nameservers.push(parse_nameserver(&value(&arg)?)?),
+            option if option.starts_with('-') => return Err(format!("unknown option {}", option)),
+            "lint" if !lint && target.is_none() => lint = true,
+            _ if target.is_some() => return Err(format!("unexpected argument {}", arg)),
+            _ => target = Some(arg),
+        }
+    }
+
+    let command = match (lint, target) {
+        (true, Some(domain)) => Command::Lint(domain.into()),
+        (true, None) => return Err("missing domain".into()),
+        (false, Some(uri)) => Command::Resolve(
+            Uri::try_from(uri.as_str()).map_err(|e| format!("invalid uri: {}", e))?,
+        ),
+        (false, None) => return Err("missing uri".into()),
+    };
+
+    Ok(Some(Args { command, supported_transports, ip_strategy, nameservers, json }))
+}
+
+fn parse_transports(list: &str) -> Result<SupportedTransports, String> {
+    list.split(',')
+        .map(|transport| {
+            transport
+                .trim()
+                .to_uppercase()
+                .parse::<Transport>()
+                .map_err(|_| format!("unknown transport {}", transport))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(SupportedTransports::only)
+}
+
+fn parse_family(policy: &str) -> Result<LookupIpStrategy, String> {
+    match policy {
+        "ipv4" => Ok(LookupIpStrategy::Ipv4Only),
+        "ipv6" => Ok(LookupIpStrategy::Ipv6Only),
+        "both" => Ok(LookupIpStrategy::Ipv4AndIpv6),
+        "ipv4-then-ipv6" => Ok(LookupIpStrategy::Ipv4thenIpv6),
+        "ipv6-then-ipv4" => Ok(LookupIpStrategy::Ipv6thenIpv4),
+        policy => Err(format!("unknown address family policy {}", policy)),
+    }
+}
+
+fn parse_nameserver(addr: &str) -> Result<SocketAddr, String> {
+    addr.parse::<SocketAddr>()
+        .or_else(|_| addr.parse().map(|ip_addr| SocketAddr::new(ip_addr, 53)))
+        .map_err(|_| format!("invalid name server address {}", addr))
+}
+
+fn dns_client(args: &Args) -> Result<TrustDnsClient, String> {
+    let opts = ResolverOpts { ip_strategy: args.ip_strategy, ..Default::default() };
+    let resolver = match args.nameservers.as_slice() {
+        [] => {
+            let (config, _) = system_conf::read_system_conf()
+                .map_err(|e| format!("can't read the system DNS configuration: {}", e))?;
+            Resolver::new(config, opts)
+        }
+        nameservers => {
+            let mut group = NameServerConfigGroup::new();
+            for nameserver in nameservers {
+                group.merge(NameServerConfigGroup::from_ips_clear(
+                    &[nameserver.ip()],
+                    nameserver.port(),
+                    true,
+                ));
+            }
+            Resolver::new(ResolverConfig::from_parts(None, vec![], group), opts)
+        }
+    }
+    .map_err(|e| format!("can't build the DNS resolver: {}", e))?;
+
+    Ok(TrustDnsClient::new(resolver))
+}
+
+fn resolve(args: &Args, uri: &Uri) -> Result<Vec<Resolved>, String> {
+    let context =
+        Context::initialize_from(uri.clone(), dns_client(args)?, args.supported_transports.clone())
+            .map_err(|e| e.to_string())?;
+    let mut lookup = Lookup::from(context);
+
+    let mut resolved = vec![];
+    let mut seen = 0;
+    while let Some(target) = futures::executor::block_on(lookup.resolve_next()) {
+        let trace = lookup.trace();
+        let events = trace[seen..].iter().filter(|event| is_query_or_answer(event)).cloned();
+
+        resolved.push(Resolved { target, events: events.collect() });
+        seen = trace.len();
+    }
+
+    Ok(resolved)
+}
+
+fn lint(args: &Args, domain: &Domain) -> Result<Report, String> {
+    let linter =
+        Linter::new(dns_client(args)?).with_supported_transports(args.supported_transports.clone());
+
+    Ok(futures::executor::block_on(linter.lint(domain.clone())))
+}
+
+fn is_query_or_answer(event: &TraceEvent) -> bool {
+    matches!(
+        event,
+        TraceEvent::NaptrQuery(_)
+            | TraceEvent::NaptrAnswer { .. }
+            | TraceEvent::SrvQuery(_)
+            | TraceEvent::SrvAnswer { .. }
+            | TraceEvent::SrvPreresolved(_)
+            | TraceEvent::AddrQuery(_)
+            | TraceEvent::AddrAnswer { .. }
+            | TraceEvent::AddrPreresolved(_)
+    )
+}
+
+fn print_human(uri: &Uri, resolved: &[Resolved]) {
+    if resolved.is_empty() {
+        println!("no targets found for {}", uri);
+        return;
+    }
+
+    println!("targets for {}:", uri);
+    for (index, Resolved { target, events }) in resolved.iter().enumerate() {
+        print!("{}. {} {}", index + 1, target.socket_addr(), target.transport);
+        match &target.server_name {
+            Some(server_name) => println!(" (server name {})", server_name),
+            None => println!(),
+        }
+        println!("   via {}", target.provenance);
+        for event in events {
+            println!("   {}", describe(event));
+        }
+    }
+}
+
+fn describe(event: &TraceEvent) -> String {
+    let list = |items: Vec<String>| match items.is_empty() {
+        true => "no records".to_string(),
+        false => items.join(", "),
+    };
+
+    match event {
+        TraceEvent::NaptrQuery(domain) => format!("? NAPTR {}", domain),
+        TraceEvent::NaptrAnswer { domain, record } => format!(
+            "! NAPTR {}: {}",
+            domain,
+            list(
+                record
+                    .iter()
+                    .flat_map(|record| &record.entries)
+                    .map(|entry| format!(
+                        "{} {} {} {}",
+                        entry.order, entry.preference, entry.services, entry.replacement
+                    ))
+                    .collect()
+            )
+        ),
+        TraceEvent::SrvQuery(domain) => format!("? SRV {}", domain),
+        TraceEvent::SrvAnswer { domain, record } => format!(
+            "! SRV {}: {}",
+            domain,
+            list(
+                record
+                    .iter()
+                    .flat_map(|record| &record.entries)
+                    .map(|entry| format!(
+                        "{} {} {}:{}",
+                        entry.priority, entry.weight, entry.target, entry.port
+                    ))
+                    .collect()
+            )
+        ),
+        TraceEvent::SrvPreresolved(record) => {
+            format!(
+                "! SRV {}: {} entries (from the NAPTR answer)",
+                record.domain,
+                record.entries.len()
+            )
+        }
+        TraceEvent::AddrQuery(domain) => format!("? A/AAAA {}", domain),
+        TraceEvent::AddrAnswer { domain, record: Ok(record) } => format!(
+            "! A/AAAA {}: {}",
+            domain,
+            list(record.ip_addrs.iter().map(ToString::to_string).collect())
+        ),
+        TraceEvent::AddrAnswer { domain, record: Err(error) } => {
+            format!("! A/AAAA {}: {}", domain, error)
+        }
+        TraceEvent::AddrPreresolved(record) => format!(
+            "! A/AAAA {}: {} (from the SRV answer)",
+            record.domain,
+            list(record.ip_addrs.iter().map(ToString::to_string).collect())
+        ),
+        event => format!("{:?}", event),
+    }
+}
+
+fn to_json(uri: &Uri, resolved: &[Resolved]) -> Value {
+    let targets = resolved
+        .iter()
+        .map(|Resolved { target, events }| {
+            json!({
+                "target": target,
+                "dns": events.iter().map(event_to_json).collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({ "uri": uri.to_string(), "targets": targets })
+}
+
+fn event_to_json(event: &TraceEvent) -> Value {
+    match event {
+        TraceEvent::NaptrQuery(domain) => json!({ "query": "NAPTR", "name": domain.to_string() }),
+        TraceEvent::NaptrAnswer { domain, record } => {
+            json!({ "answer": "NAPTR", "name": domain.to_string(), "record": record })
+        }
+        TraceEvent::SrvQuery(domain) => json!({ "query": "SRV", "name": domain }),
+        TraceEvent::SrvAnswer { domain, record } => {
+            json!({ "answer": "SRV", "name": domain, "record": record })
+        }
+        TraceEvent::SrvPreresolved(record) => {
+            json!({ "answer": "SRV", "name": record.domain, "record": record, "preresolved": true })
+        }
+        TraceEvent::AddrQuery(domain) => json!({ "query": "A/AAAA", "name": domain.to_string() }),
+        TraceEvent::AddrAnswer { domain, record: Ok(record) } => {
+            json!({ "answer": "A/AAAA", "name": domain.to_string(), "record": record })
+        }
+        TraceEvent::AddrAnswer { domain, record: Err(error) } => {
+            json!({ "answer": "A/AAAA", "name": domain.to_string(), "error": error.to_string() })
+        }
+        TraceEvent::AddrPreresolved(record) => json!({
+            "answer": "A/AAAA",
+            "name": record.domain.to_string(),
+            "record": record,
+            "preresolved": true
+        }),
+        event => json!({ "event": format!("{:?}", event) }),
+    }
+}
+
+fn print_report(report: &Report) {
+    if report.problems.is_empty() {
+        println!("no problems found for {}", report.domain);
+        return;
+    }
+
+    println!("problems found for {}:", report.domain);
+    for problem in report.problems.iter() {
+        println!("{}: {}", severity_of(problem.severity()), problem);
+    }
+}
+
+fn report_to_json(report: &Report) -> Value {
+    let problems = report
+        .problems
+        .iter()
+        .map(|problem| {
+            json!({ "severity": severity_of(problem.severity()), "problem": problem.to_string() })
+        })
+        .collect::<Vec<_>>();
+
+    json!({ "domain": report.domain.to_string(), "problems": problems })
+}
+
+fn severity_of(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
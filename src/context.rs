@@ -1,11 +1,16 @@
-use crate::DnsClient;
+use crate::{DnsClient, SrvSelection};
 use rsip::{Error, Host, Port, Scheme, Transport, Uri};
+use std::time::Duration;
 
 /// This is the main context struct that is used by the [Lookup](super::Lookup) to figure out what
 /// procedures it should apply.
-/// It can manually initialized by populating every field, or by using the
+/// It can be manually initialized with [Context::new] and its `with_*` methods, or by using the
 /// [Context::initialize_from] method which can be handy if you already have the URI of the host.
 ///
+/// It can also be initialized as a struct, in which case the `limits`, `prefetch` and
+/// `srv_selection` settings can be left to their defaults with `..Default::default()` (when the
+/// dns client implements [Default]).
+///
 /// [Context::initialize_from] can return an error if the URI transport constraints and `supported_transports`
 /// don't overlap.
 #[derive(Debug, Clone, Default)]
@@ -16,6 +21,29 @@ pub struct Context<C: DnsClient> {
     pub transport: Option<Transport>,
     pub dns_client: C,
     pub supported_transports: SupportedTransports,
+    /// See [Context::with_limits].
+    pub limits: Limits,
+    /// See [Context::with_prefetch].
+    pub prefetch: usize,
+    /// See [Context::with_srv_selection].
+    pub srv_selection: SrvSelection,
+}
+
+/// Limits on how much DNS work a [Lookup](super::Lookup) can do, since a bare domain can need
+/// many queries and, with a slow resolver, those can take longer than the SIP transaction timer B
+/// (32s by default).
+///
+/// Once a limit is reached, the lookup doesn't start any new query and yields only the targets
+/// it has already resolved. The reason it stopped is given by
+/// [Lookup::stop_reason](super::Lookup::stop_reason). Both limits are off by default.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Limits {
+    /// The maximum number of queries sent to the [DnsClient]. Records that came along in the
+    /// additional section of an answer don't count.
+    pub max_queries: Option<usize>,
+    /// The total time the lookup can take, counting from its creation. A query still in flight
+    /// when the deadline is reached is abandoned.
+    pub deadline: Option<Duration>,
 }
 
 impl<C: DnsClient> Context<C> {
@@ -53,6 +81,22 @@ impl<C: DnsClient> Context<C> {
 }
 
 impl<C: DnsClient> Context<C> {
+    /// Creates an insecure context for the given host, with no port or transport constraints,
+    /// where any transport is supported.
+    pub fn new(host: Host, dns_client: C) -> Self {
+        Self {
+            secure: false,
+            host,
+            port: None,
+            transport: None,
+            dns_client,
+            supported_transports: Default::default(),
+            limits: Default::default(),
+            prefetch: 0,
+            srv_selection: Default::default(),
+        }
+    }
+
     pub fn initialize_from(
         uri: Uri,
         dns_client: C,
@@ -78,9 +122,56 @@ impl<C: DnsClient> Context<C> {
             port: uri.host_with_port.port,
             dns_client,
             supported_transports,
+            limits: Default::default(),
+            prefetch: 0,
+            srv_selection: Default::default(),
         })
     }
 
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    pub fn with_port(mut self, port: Port) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn with_transport(mut self, transport: Transport) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    pub fn with_supported_transports(mut self, supported_transports: SupportedTransports) -> Self {
+        self.supported_transports = supported_transports;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets how many of the upcoming SRV and A/AAAA queries of the [Lookup](super::Lookup) are
+    /// sent ahead of time, while the caller is still using the current targets. Queries are still
+    /// sent in the same order and the targets are yielded in the same order as in the lazy mode.
+    /// The default, 0, keeps the lookup lazy.
+    ///
+    /// Since `rsip-dns` doesn't spawn any tasks, queries in flight make progress only while the
+    /// lookup is polled; that's usually enough, as DNS clients like trust-dns send a query on the
+    /// first poll.
+    pub fn with_prefetch(mut self, prefetch: usize) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Sets how the entries of each SRV record are ordered before being tried.
+    pub fn with_srv_selection(mut self, srv_selection: SrvSelection) -> Self {
+        self.srv_selection = srv_selection;
+        self
+    }
+
     pub(crate) fn default_transport(&self) -> Transport {
         match self.transport {
             Some(transport) => transport,
@@ -102,7 +193,7 @@ fn secure_from_scheme(scheme: Scheme) -> Result<bool, rsip::Error> {
 
 /// Simple struct that allows you to specify whether all `rsip` transports are available or only
 /// specific ones. Used here as a type safety to order to avoid edge cases of `Option<Vec<T>>`..
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SupportedTransports(Vec<Transport>);
 
 impl SupportedTransports {
@@ -9,6 +9,12 @@ use rsip::{Domain, Error};
 ///
 /// Note that whether [DnsClient::ip_lookup] queries for an A or an AAAA or both records is up
 /// to the DNS client used.
+///
+/// **Breaking change:** a [Lookup](super::Lookup) (and the resolvables it's made of) needs the
+/// client to be `'static`, even when nothing is prefetched, since the prefetched queries (see
+/// [Context::with_prefetch](super::Context::with_prefetch)) own a clone of the client to stay in
+/// flight across [resolve_next](super::ResolvableExt::resolve_next) calls. A client that borrows
+/// its resolver (like `&'a Resolver`) has to own it, or an `Arc` of it, instead.
 #[async_trait]
 pub trait DnsClient: Clone + Sync + Send {
     // returns an Option since RFC 3263 alg can continue even without this
@@ -16,4 +22,32 @@ pub trait DnsClient: Clone + Sync + Send {
     // returns an Option since RFC 3263 alg can continue even without this
     async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord>;
     async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error>;
+
+    /// Same as [DnsClient::naptr_lookup], but tells apart a failed query (like a timeout), given
+    /// as an error, from an answer without records, given as `None`. Combinators like
+    /// [FallbackDnsClient](super::FallbackDnsClient) rely on it to decide whether to try another
+    /// client. The default implementation can't tell them apart, hence it never fails.
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        Ok(self.naptr_lookup(domain).await)
+    }
+
+    /// Same as [DnsClient::try_naptr_lookup], for SRV queries.
+    async fn try_srv_lookup(&self, domain: SrvDomain) -> Result<Option<SrvRecord>, Error> {
+        Ok(self.srv_lookup(domain).await)
+    }
+
+    /// Same as [DnsClient::try_naptr_lookup], for A/AAAA queries. The default implementation
+    /// considers any error of [DnsClient::ip_lookup] a failed query.
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        self.ip_lookup(domain).await.map(Some)
+    }
+
+    /// Returns the canonical name of the domain if the domain is an alias (it has a CNAME
+    /// record), `None` otherwise. A [Lookup](super::Lookup) never needs it, it's used by the
+    /// [Linter](super::Linter) to find SRV targets that are aliases, which
+    /// [RFC 2782](https://datatracker.ietf.org/doc/html/rfc2782) forbids. The default
+    /// implementation can't tell, hence it never finds any.
+    async fn try_cname_lookup(&self, _domain: Domain) -> Result<Option<Domain>, Error> {
+        Ok(None)
+    }
 }
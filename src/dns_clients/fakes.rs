@@ -0,0 +1,243 @@
+use async_trait::async_trait;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use super::{key_of, QueryType};
+use crate::{records::*, DnsClient};
+use rsip::{Domain, Error};
+
+/// A [DnsClient] that answers from static NAPTR, SRV, address and CNAME records, meant for tests.
+/// Queries for names without records get an empty answer (or an error, for
+/// [DnsClient::ip_lookup]), unless their query type is set to panic with
+/// [StaticDnsClient::panic_on], which is useful to assert that a code path never makes a specific
+/// kind of query.
+///
+/// Names are matched case insensitively, ignoring any trailing dot.
+///
+/// ```
+/// use rsip_dns::{records::AddrRecord, QueryType, StaticDnsClient};
+///
+/// let dns_client = StaticDnsClient::new()
+///     .with_addr(AddrRecord {
+///         domain: "example.com".into(),
+///         ip_addrs: vec!["10.0.0.1".parse().unwrap()],
+///     })
+///     .panic_on(QueryType::Naptr);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StaticDnsClient {
+    naptr: HashMap<String, NaptrRecord>,
+    srv: HashMap<String, SrvRecord>,
+    addr: HashMap<String, AddrRecord>,
+    cname: HashMap<String, Domain>,
+    panic_on: HashSet<QueryType>,
+}
+
+impl StaticDnsClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_naptr(mut self, record: NaptrRecord) -> Self {
+        self.naptr.insert(key_of(&record.domain), record);
+        self
+    }
+
+    pub fn with_srv(mut self, record: SrvRecord) -> Self {
+        self.srv.insert(key_of(&record.domain.to_string().into()), record);
+        self
+    }
+
+    /// Adds the A/AAAA answer of the record's domain. Adding more records for the same domain
+    /// appends their addresses.
+    pub fn with_addr(mut self, record: AddrRecord) -> Self {
+        match self.addr.get_mut(&key_of(&record.domain)) {
+            Some(existing) => existing.ip_addrs.extend(record.ip_addrs),
+            None => {
+                self.addr.insert(key_of(&record.domain), record);
+            }
+        }
+        self
+    }
+
+    /// Makes the domain an alias of the canonical one. Only [DnsClient::try_cname_lookup] is
+    /// affected, aliases are not followed by the other queries.
+    pub fn with_cname(mut self, domain: Domain, canonical: Domain) -> Self {
+        self.cname.insert(key_of(&domain), canonical);
+        self
+    }
+
+    /// Makes any query of the given type panic.
+    pub fn panic_on(mut self, query_type: QueryType) -> Self {
+        self.panic_on.insert(query_type);
+        self
+    }
+
+    fn check(&self, query_type: QueryType, name: &dyn std::fmt::Display) {
+        if self.panic_on.contains(&query_type) {
+            panic!("unexpected {:?} query for {}", query_type, name)
+        }
+    }
+}
+
+#[async_trait]
+impl DnsClient for StaticDnsClient {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        self.check(QueryType::Naptr, &domain);
+        self.naptr.get(&key_of(&domain)).cloned()
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        self.check(QueryType::Srv, &domain);
+        self.srv.get(&key_of(&domain.to_string().into())).cloned()
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        self.try_ip_lookup(domain.clone())
+            .await?
+            .ok_or_else(|| Error::Unexpected(format!("no A/AAAA records for {}", domain)))
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        self.check(QueryType::Addr, &domain);
+        Ok(self.addr.get(&key_of(&domain)).cloned())
+    }
+
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        self.check(QueryType::Cname, &domain);
+        Ok(self.cname.get(&key_of(&domain)).cloned())
+    }
+}
+
+/// A [DnsClient] wrapper that records the queries made to the inner client, so that tests can
+/// check which names were queried and how many times, using [SpyDnsClient::calls] or by
+/// setting expectations with [SpyDnsClient::expect] and checking them with
+/// [SpyDnsClient::verify]. Clones share the same recorded calls.
+#[derive(Debug, Clone)]
+pub struct SpyDnsClient<C: DnsClient> {
+    inner: C,
+    calls: Arc<Mutex<Vec<(QueryType, String)>>>,
+    expectations: HashMap<QueryType, usize>,
+}
+
+impl<C: DnsClient> SpyDnsClient<C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner, calls: Default::default(), expectations: Default::default() }
+    }
+
+    /// Expects exactly the given number of queries of the given type, checked by
+    /// [SpyDnsClient::verify].
+    pub fn expect(mut self, query_type: QueryType, count: usize) -> Self {
+        self.expectations.insert(query_type, count);
+        self
+    }
+
+    /// Returns the queries made so far, in order, along with the queried name.
+    pub fn calls(&self) -> Vec<(QueryType, String)> {
+        self.calls.lock().expect("calls lock poisoned").clone()
+    }
+
+    /// Returns the names queried so far with the given query type, in order.
+    pub fn calls_of(&self, query_type: QueryType) -> Vec<String> {
+        self.calls()
+            .into_iter()
+            .filter(|(call_type, _)| *call_type == query_type)
+            .map(|(_, name)| name)
+            .collect()
+    }
+
+    /// Panics if the number of queries of any type doesn't match the expected one.
+    pub fn verify(&self) {
+        let mut mismatches = self
+            .expectations
+            .iter()
+            .map(|(query_type, count)| (query_type, count, self.calls_of(*query_type)))
+            .filter(|(_, count, calls)| calls.len() != **count)
+            .map(|(query_type, count, calls)| {
+                format!(
+                    "expected {} {:?} queries, got {}: {:?}",
+                    count,
+                    query_type,
+                    calls.len(),
+                    calls
+                )
+            })
+            .collect::<Vec<_>>();
+        mismatches.sort();
+
+        if !mismatches.is_empty() {
+            panic!("{}", mismatches.join(", "))
+        }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn record(&self, query_type: QueryType, name: String) {
+        self.calls.lock().expect("calls lock poisoned").push((query_type, name));
+    }
+}
+
+#[async_trait]
+impl<C: DnsClient> DnsClient for SpyDnsClient<C> {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        self.record(QueryType::Naptr, domain.to_string());
+        self.inner.naptr_lookup(domain).await
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        self.record(QueryType::Srv, domain.to_string());
+        self.inner.srv_lookup(domain).await
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        self.record(QueryType::Addr, domain.to_string());
+        self.inner.ip_lookup(domain).await
+    }
+
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        self.record(QueryType::Naptr, domain.to_string());
+        self.inner.try_naptr_lookup(domain).await
+    }
+
+    async fn try_srv_lookup(&self, domain: SrvDomain) -> Result<Option<SrvRecord>, Error> {
+        self.record(QueryType::Srv, domain.to_string());
+        self.inner.try_srv_lookup(domain).await
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        self.record(QueryType::Addr, domain.to_string());
+        self.inner.try_ip_lookup(domain).await
+    }
+
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        self.record(QueryType::Cname, domain.to_string());
+        self.inner.try_cname_lookup(domain).await
+    }
+}
+
+/// A [DnsClient] that panics on any query, to assert that a code path never hits the DNS.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PanicDnsClient;
+
+#[async_trait]
+impl DnsClient for PanicDnsClient {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        unexpected("NAPTR", domain)
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        unexpected("SRV", domain)
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        unexpected("A/AAAA", domain)
+    }
+}
+
+fn unexpected<T>(query_type: &str, name: impl std::fmt::Display) -> T {
+    panic!("unexpected {} query for {}", query_type, name)
+}
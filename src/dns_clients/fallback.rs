@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use futures::future::{self, Either, Future, FutureExt};
+use futures_timer::Delay;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{records::*, DnsClient};
+use rsip::{Domain, Error};
+
+/// A [DnsClient] that tries a list of inner clients in order, moving to the next one when a query
+/// fails or times out, so that a dead primary resolver doesn't silently turn every
+/// [Lookup](crate::Lookup) into an empty one.
+///
+/// Only failed queries trigger the fallback: an answer without records is a valid answer and is
+/// returned as is. Telling the two apart relies on the `try_*` methods of the inner clients (see
+/// [DnsClient::try_naptr_lookup]).
+///
+/// A client that fails is considered unhealthy and is skipped for a cooldown period, unless all
+/// clients are unhealthy, in which case they are all tried in order. A client becomes healthy
+/// again as soon as it answers. Clones share the same health state.
+#[derive(Debug, Clone)]
+pub struct FallbackDnsClient<C: DnsClient> {
+    clients: Vec<C>,
+    timeout: Duration,
+    cooldown: Duration,
+    unhealthy_until: Arc<Mutex<Vec<Option<Instant>>>>,
+}
+
+impl<C: DnsClient> FallbackDnsClient<C> {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+    pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+    pub fn new(clients: Vec<C>) -> Self {
+        Self {
+            unhealthy_until: Arc::new(Mutex::new(vec![None; clients.len()])),
+            clients,
+            timeout: Self::DEFAULT_TIMEOUT,
+            cooldown: Self::DEFAULT_COOLDOWN,
+        }
+    }
+
+    /// Sets how long each client is given to answer a query, before moving to the next one.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets for how long a client that failed is skipped.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn clients(&self) -> &[C] {
+        self.clients.as_slice()
+    }
+
+    /// Returns whether each client (in the order given) is currently healthy.
+    pub fn health(&self) -> Vec<bool> {
+        let now = Instant::now();
+
+        self.unhealthy_until
+            .lock()
+            .expect("health lock poisoned")
+            .iter()
+            .map(|until| !matches!(until, Some(until) if *until > now))
+            .collect()
+    }
+
+    //healthy clients first, then the unhealthy ones, if all of them are unhealthy
+    fn order(&self) -> Vec<usize> {
+        let health = self.health();
+        let healthy = (0..self.clients.len()).filter(|index| health[*index]).collect::<Vec<_>>();
+
+        match healthy.is_empty() {
+            true => (0..self.clients.len()).collect(),
+            false => healthy,
+        }
+    }
+
+    fn set_health(&self, index: usize, healthy: bool) {
+        let mut unhealthy_until = self.unhealthy_until.lock().expect("health lock poisoned");
+        unhealthy_until[index] = match healthy {
+            true => None,
+            false => Some(Instant::now() + self.cooldown),
+        };
+    }
+
+    async fn query<T, F, Fut>(&self, query: F) -> Result<Option<T>, Error>
+    where
+        T: Send,
+        F: Fn(C) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<Option<T>, Error>> + Send,
+    {
+        let mut last_error = Error::Unexpected("no dns clients to query".into());
+
+        for index in self.order() {
+            let query = query(self.clients[index].clone()).boxed();
+
+            match future::select(query, Delay::new(self.timeout)).await {
+                Either::Left((Ok(answer), _)) => {
+                    self.set_health(index, true);
+                    return Ok(answer);
+                }
+                Either::Left((Err(error), _)) => {
+                    self.set_health(index, false);
+                    last_error = error;
+                }
+                Either::Right(_) => {
+                    self.set_health(index, false);
+                    last_error = Error::Unexpected(format!(
+                        "dns query timed out after {}ms",
+                        self.timeout.as_millis()
+                    ));
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+}
+
+#[async_trait]
+impl<C: DnsClient> DnsClient for FallbackDnsClient<C> {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        self.try_naptr_lookup(domain).await.ok().flatten()
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        self.try_srv_lookup(domain).await.ok().flatten()
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        self.try_ip_lookup(domain.clone())
+            .await?
+            .ok_or_else(|| Error::Unexpected(format!("no A/AAAA records for {}", domain)))
+    }
+
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        self.query(|client| {
+            let domain = domain.clone();
+            async move { client.try_naptr_lookup(domain).await }
+        })
+        .await
+    }
+
+    async fn try_srv_lookup(&self, domain: SrvDomain) -> Result<Option<SrvRecord>, Error> {
+        self.query(|client| {
+            let domain = domain.clone();
+            async move { client.try_srv_lookup(domain).await }
+        })
+        .await
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        self.query(|client| {
+            let domain = domain.clone();
+            async move { client.try_ip_lookup(domain).await }
+        })
+        .await
+    }
+
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        self.query(|client| {
+            let domain = domain.clone();
+            async move { client.try_cname_lookup(domain).await }
+        })
+        .await
+    }
+}
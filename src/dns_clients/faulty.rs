@@ -0,0 +1,215 @@
+use async_trait::async_trait;
+use futures_timer::Delay;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::QueryType;
+use crate::{records::*, DnsClient};
+use rsip::{Domain, Error};
+
+/// A fault injected by a [FaultyDnsClient] to a query.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Fault {
+    /// The answer is delayed by the given duration.
+    Latency(Duration),
+    /// The query fails with a timeout error after the given duration.
+    Timeout(Duration),
+    /// The query fails right away, like a `SERVFAIL` response.
+    ServFail,
+    /// The answer has no records.
+    Empty,
+    /// Only the first given number of entries of a SRV answer are kept. Doesn't affect other
+    /// query types.
+    TruncatedSrv(usize),
+    /// The entries (or ip addresses) of the answer are shuffled.
+    Shuffled,
+}
+
+/// A [DnsClient] wrapper that injects faults (latency, timeouts, failures, empty, truncated or
+/// shuffled answers) to the queries of the inner client, to check how the code built on top of
+/// it copes with a misbehaving DNS.
+///
+/// Faults are given per [QueryType], either as probabilities ([FaultyDnsClient::with_fault]) or
+/// as scripts ([FaultyDnsClient::with_script]) that list the faults of the next queries, one by
+/// one. Scripts take precedence over probabilities, until they run out.
+///
+/// All randomness comes from a seeded RNG, so a failing run can be reproduced by reusing its
+/// seed. Clones share the same RNG and scripts, so the faults drawn depend on the order the
+/// queries reach the client: a run is only reproduced when its queries are not concurrent, like
+/// a [Lookup](crate::Lookup) with `prefetch` set to 0.
+#[derive(Debug, Clone)]
+pub struct FaultyDnsClient<C: DnsClient> {
+    inner: C,
+    state: Arc<Mutex<FaultState>>,
+}
+
+#[derive(Debug)]
+struct FaultState {
+    rng: StdRng,
+    probabilities: HashMap<QueryType, Vec<(f64, Fault)>>,
+    scripts: HashMap<QueryType, VecDeque<Vec<Fault>>>,
+}
+
+impl<C: DnsClient> FaultyDnsClient<C> {
+    pub fn new(inner: C, seed: u64) -> Self {
+        Self {
+            inner,
+            state: Arc::new(Mutex::new(FaultState {
+                rng: StdRng::seed_from_u64(seed),
+                probabilities: Default::default(),
+                scripts: Default::default(),
+            })),
+        }
+    }
+
+    /// Injects the fault to queries of the given type with the given probability (0.0 to 1.0,
+    /// values outside are clamped). Each fault is drawn independently, hence a query might get
+    /// more than one.
+    ///
+    /// Panics if the probability is NaN.
+    pub fn with_fault(self, query_type: QueryType, probability: f64, fault: Fault) -> Self {
+        assert!(!probability.is_nan(), "fault probability can't be NaN");
+
+        self.lock()
+            .probabilities
+            .entry(query_type)
+            .or_default()
+            .push((probability.clamp(0.0, 1.0), fault));
+        self
+    }
+
+    /// Injects the given faults to the next queries of the given type: the first item to the
+    /// first query, the second to the second one etc. An empty item leaves the query as is.
+    pub fn with_script(self, query_type: QueryType, script: Vec<Vec<Fault>>) -> Self {
+        self.lock().scripts.entry(query_type).or_default().extend(script);
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FaultState> {
+        self.state.lock().expect("fault state lock poisoned")
+    }
+
+    fn faults_for(&self, query_type: QueryType) -> Vec<Fault> {
+        let mut state = self.lock();
+
+        if let Some(faults) = state.scripts.get_mut(&query_type).and_then(VecDeque::pop_front) {
+            return faults;
+        }
+
+        let probabilities = state.probabilities.get(&query_type).cloned().unwrap_or_default();
+        probabilities
+            .into_iter()
+            .filter(|(probability, _)| state.rng.gen_bool(*probability))
+            .map(|(_, fault)| fault)
+            .collect()
+    }
+
+    fn shuffle<T>(&self, items: &mut [T]) {
+        items.shuffle(&mut self.lock().rng)
+    }
+
+    //applies the faults that come before the answer (delays, failures and empty answers),
+    //returns whether the inner client should be queried
+    async fn before_answer(&self, faults: &[Fault]) -> Result<bool, Error> {
+        for fault in faults {
+            match fault {
+                Fault::Latency(duration) => Delay::new(*duration).await,
+                Fault::Timeout(duration) => {
+                    Delay::new(*duration).await;
+                    return Err(Error::Unexpected("injected fault: request timed out".into()));
+                }
+                _ => (),
+            }
+        }
+
+        match faults {
+            faults if faults.contains(&Fault::ServFail) => {
+                Err(Error::Unexpected("injected fault: SERVFAIL".into()))
+            }
+            faults if faults.contains(&Fault::Empty) => Ok(false),
+            _ => Ok(true),
+        }
+    }
+}
+
+#[async_trait]
+impl<C: DnsClient> DnsClient for FaultyDnsClient<C> {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        self.try_naptr_lookup(domain).await.ok().flatten()
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        self.try_srv_lookup(domain).await.ok().flatten()
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        self.try_ip_lookup(domain.clone())
+            .await?
+            .ok_or_else(|| Error::Unexpected(format!("no A/AAAA records for {}", domain)))
+    }
+
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        let faults = self.faults_for(QueryType::Naptr);
+        if !self.before_answer(&faults).await? {
+            return Ok(None);
+        }
+
+        let mut record = self.inner.try_naptr_lookup(domain).await?;
+        if let (Some(record), true) = (&mut record, faults.contains(&Fault::Shuffled)) {
+            self.shuffle(&mut record.entries);
+        }
+
+        Ok(record)
+    }
+
+    async fn try_srv_lookup(&self, domain: SrvDomain) -> Result<Option<SrvRecord>, Error> {
+        let faults = self.faults_for(QueryType::Srv);
+        if !self.before_answer(&faults).await? {
+            return Ok(None);
+        }
+
+        let mut record = self.inner.try_srv_lookup(domain).await?;
+        if let Some(record) = &mut record {
+            for fault in faults.iter() {
+                match fault {
+                    Fault::TruncatedSrv(len) => record.entries.truncate(*len),
+                    Fault::Shuffled => self.shuffle(&mut record.entries),
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(record)
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        let faults = self.faults_for(QueryType::Addr);
+        if !self.before_answer(&faults).await? {
+            return Ok(None);
+        }
+
+        let mut record = self.inner.try_ip_lookup(domain).await?;
+        if let (Some(record), true) = (&mut record, faults.contains(&Fault::Shuffled)) {
+            self.shuffle(&mut record.ip_addrs);
+        }
+
+        Ok(record)
+    }
+
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        let faults = self.faults_for(QueryType::Cname);
+        if !self.before_answer(&faults).await? {
+            return Ok(None);
+        }
+
+        self.inner.try_cname_lookup(domain).await
+    }
+}
@@ -0,0 +1,40 @@
+//! This module holds [DnsClient](crate::DnsClient) trait implementations that do not depend on
+//! any DNS library, either standalone or wrapping another client.
+
+#[cfg(feature = "test-utils")]
+mod fakes;
+mod fallback;
+#[cfg(feature = "test-utils")]
+mod faulty;
+mod overrides;
+#[cfg(feature = "serde")]
+mod record_replay;
+mod split_horizon;
+mod zone_file;
+
+#[cfg(feature = "test-utils")]
+pub use fakes::{PanicDnsClient, SpyDnsClient, StaticDnsClient};
+pub use fallback::FallbackDnsClient;
+#[cfg(feature = "test-utils")]
+pub use faulty::{Fault, FaultyDnsClient};
+pub use overrides::OverrideDnsClient;
+#[cfg(feature = "serde")]
+pub use record_replay::{RecordedAnswer, Recording, RecordingDnsClient, ReplayDnsClient};
+pub use split_horizon::SplitHorizonDnsClient;
+pub use zone_file::ZoneFileDnsClient;
+
+use rsip::Domain;
+
+/// The type of a DNS query, as made through the [DnsClient](crate::DnsClient) methods.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum QueryType {
+    Naptr,
+    Srv,
+    Addr,
+    Cname,
+}
+
+//names are kept lowercase, without the trailing dot
+fn key_of(domain: &Domain) -> String {
+    domain.to_string().trim_end_matches('.').to_ascii_lowercase()
+}
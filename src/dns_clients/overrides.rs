@@ -0,0 +1,168 @@
+use async_trait::async_trait;
+use std::{collections::HashMap, net::IpAddr, path::Path, sync::Arc};
+
+use super::key_of;
+use crate::{records::*, DnsClient};
+use rsip::{Domain, Error};
+
+/// A [DnsClient] that answers from static NAPTR, SRV and address records for specific names and
+/// falls through to the inner client for everything else, like pinning `carrier.example.net` to
+/// a lab SBC while all other names still go through the real resolver.
+///
+/// Names are matched case insensitively, ignoring any trailing dot. The address overrides can
+/// also be loaded from an `/etc/hosts` formatted file, using [OverrideDnsClient::with_hosts_file].
+///
+/// ```
+/// use rsip_dns::{records::AddrRecord, OverrideDnsClient, ZoneFileDnsClient};
+///
+/// let dns_client = OverrideDnsClient::new(ZoneFileDnsClient::default())
+///     .with_addr(AddrRecord {
+///         domain: "carrier.example.net".into(),
+///         ip_addrs: vec!["10.0.0.1".parse().unwrap()],
+///     })
+///     .with_hosts("10.0.0.2 sbc.lab sbc # the lab SBC")
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct OverrideDnsClient<C: DnsClient> {
+    inner: C,
+    overrides: Arc<Overrides>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Overrides {
+    naptr: HashMap<String, NaptrRecord>,
+    srv: HashMap<String, SrvRecord>,
+    addr: HashMap<String, AddrRecord>,
+}
+
+impl<C: DnsClient> OverrideDnsClient<C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner, overrides: Default::default() }
+    }
+
+    /// Overrides the NAPTR answer of the record's domain.
+    pub fn with_naptr(mut self, record: NaptrRecord) -> Self {
+        Arc::make_mut(&mut self.overrides).naptr.insert(key_of(&record.domain), record);
+        self
+    }
+
+    /// Overrides the SRV answer of the record's SRV domain.
+    pub fn with_srv(mut self, record: SrvRecord) -> Self {
+        let key = key_of(&record.domain.to_string().into());
+        Arc::make_mut(&mut self.overrides).srv.insert(key, record);
+        self
+    }
+
+    /// Overrides the A/AAAA answer of the record's domain. Adding more records for the same
+    /// domain appends their addresses.
+    pub fn with_addr(mut self, record: AddrRecord) -> Self {
+        let overrides = Arc::make_mut(&mut self.overrides);
+        match overrides.addr.get_mut(&key_of(&record.domain)) {
+            Some(existing) => existing.ip_addrs.extend(record.ip_addrs),
+            None => {
+                overrides.addr.insert(key_of(&record.domain), record);
+            }
+        }
+        self
+    }
+
+    /// Adds address overrides out of `/etc/hosts` formatted contents: an ip address followed by
+    /// one or more names (canonical name and aliases) per line, with `#` starting a comment.
+    /// Any parse error mentions the offending line.
+    pub fn with_hosts(self, contents: &str) -> Result<Self, Error> {
+        let mut client = self;
+
+        for (index, line) in contents.lines().enumerate() {
+            let mut parts = line.split('#').next().unwrap_or_default().split_whitespace();
+            let ip_addr = match parts.next() {
+                Some(ip_addr) => ip_addr.parse::<IpAddr>().map_err(|_| {
+                    Error::ParseError(format!("line {}: invalid ip address {}", index + 1, ip_addr))
+                })?,
+                None => continue,
+            };
+
+            let names = parts.collect::<Vec<_>>();
+            if names.is_empty() {
+                return Err(Error::ParseError(format!(
+                    "line {}: missing host name for {}",
+                    index + 1,
+                    ip_addr
+                )));
+            }
+
+            for name in names {
+                client =
+                    client.with_addr(AddrRecord { domain: name.into(), ip_addrs: vec![ip_addr] });
+            }
+        }
+
+        Ok(client)
+    }
+
+    /// Same as [OverrideDnsClient::with_hosts], reading the contents of the given file.
+    pub fn with_hosts_file(self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let contents = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            Error::Unexpected(format!("could not read {}: {}", path.as_ref().display(), e))
+        })?;
+
+        self.with_hosts(&contents)
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<C: DnsClient> DnsClient for OverrideDnsClient<C> {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        match self.overrides.naptr.get(&key_of(&domain)) {
+            Some(record) => Some(record.clone()),
+            None => self.inner.naptr_lookup(domain).await,
+        }
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        match self.overrides.srv.get(&key_of(&domain.to_string().into())) {
+            Some(record) => Some(record.clone()),
+            None => self.inner.srv_lookup(domain).await,
+        }
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        match self.overrides.addr.get(&key_of(&domain)) {
+            Some(record) => Ok(record.clone()),
+            None => self.inner.ip_lookup(domain).await,
+        }
+    }
+
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        match self.overrides.naptr.get(&key_of(&domain)) {
+            Some(record) => Ok(Some(record.clone())),
+            None => self.inner.try_naptr_lookup(domain).await,
+        }
+    }
+
+    async fn try_srv_lookup(&self, domain: SrvDomain) -> Result<Option<SrvRecord>, Error> {
+        match self.overrides.srv.get(&key_of(&domain.to_string().into())) {
+            Some(record) => Ok(Some(record.clone())),
+            None => self.inner.try_srv_lookup(domain).await,
+        }
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        match self.overrides.addr.get(&key_of(&domain)) {
+            Some(record) => Ok(Some(record.clone())),
+            None => self.inner.try_ip_lookup(domain).await,
+        }
+    }
+
+    //an overridden address is never an alias
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        match self.overrides.addr.contains_key(&key_of(&domain)) {
+            true => Ok(None),
+            false => self.inner.try_cname_lookup(domain).await,
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Write,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use super::key_of;
+use crate::{records::*, DnsClient};
+use rsip::{Domain, Error};
+
+/// A single DNS query of a [Recording], along with its answer: either some records, no records
+/// (`Ok(None)`) or a failure, kept as its error message.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "query", rename_all = "lowercase")]
+pub enum RecordedAnswer {
+    Naptr {
+        #[serde(with = "crate::serialization::domain")]
+        domain: Domain,
+        answer: Result<Option<NaptrRecord>, String>,
+    },
+    Srv {
+        domain: SrvDomain,
+        answer: Result<Option<SrvRecord>, String>,
+    },
+    Addr {
+        #[serde(with = "crate::serialization::domain")]
+        domain: Domain,
+        answer: Result<Option<AddrRecord>, String>,
+    },
+    Cname {
+        #[serde(with = "crate::serialization::domain")]
+        domain: Domain,
+        #[serde(with = "crate::serialization::cname_answer")]
+        answer: Result<Option<Domain>, String>,
+    },
+}
+
+/// The DNS answers captured by a [RecordingDnsClient], in the order the queries were made.
+///
+/// A recording is saved and loaded as JSON lines, one [RecordedAnswer] per line, using the
+/// representation of the records described in the [serde](crate#serde) section:
+///
+/// ```text
+/// {"query":"srv","domain":"_sip._udp.example.com","answer":{"Ok":null}}
+/// {"query":"addr","domain":"sip1.example.com","answer":{"Err":"request timed out"}}
+/// {"query":"cname","domain":"www.example.com","answer":{"Ok":"sip1.example.com"}}
+/// ```
+///
+/// It's available under the `serde` feature flag, as are the [RecordingDnsClient] and the
+/// [ReplayDnsClient].
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Recording(Vec<RecordedAnswer>);
+
+/// A [DnsClient] that passes every query to the inner client and records the query along with
+/// its answer, including failures, so that it can be replayed later with a [ReplayDnsClient].
+///
+/// The recording is kept in memory (see [RecordingDnsClient::recording]) and, when created
+/// using [RecordingDnsClient::with_file], also appended to the file as each answer comes in.
+#[derive(Debug, Clone)]
+pub struct RecordingDnsClient<C: DnsClient> {
+    inner: C,
+    recording: Arc<Mutex<Recording>>,
+    file: Option<Arc<Mutex<File>>>,
+}
+
+/// A [DnsClient] that serves back the answers of a [Recording], without touching the network.
+///
+/// When a query was recorded more than once, its answers are served in the recorded order, the
+/// last one being repeated once they run out. A query that is not part of the recording panics,
+/// since the replay can't be trusted anymore: the lookup would take a different path than the
+/// recorded one, as NAPTR and SRV queries can't fail.
+#[derive(Debug, Clone)]
+pub struct ReplayDnsClient {
+    answers: Arc<Mutex<HashMap<String, Vec<RecordedAnswer>>>>,
+}
+
+impl RecordedAnswer {
+    fn key(&self) -> String {
+        match self {
+            Self::Naptr { domain, .. } => format!("naptr {}", key_of(domain)),
+            Self::Srv { domain, .. } => format!("srv {}", key_of(&domain.to_string().into())),
+            Self::Addr { domain, .. } => format!("addr {}", key_of(domain)),
+            Self::Cname { domain, .. } => format!("cname {}", key_of(domain)),
+        }
+    }
+}
+
+impl Recording {
+    pub fn answers(&self) -> &[RecordedAnswer] {
+        self.0.as_slice()
+    }
+
+    pub fn push(&mut self, answer: RecordedAnswer) {
+        self.0.push(answer)
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        std::fs::read_to_string(path.as_ref())
+            .map_err(|e| {
+                Error::Unexpected(format!("could not read {}: {}", path.as_ref().display(), e))
+            })?
+            .parse()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        std::fs::write(path.as_ref(), self.to_string()).map_err(|e| {
+            Error::Unexpected(format!("could not write {}: {}", path.as_ref().display(), e))
+        })
+    }
+}
+
+impl From<Vec<RecordedAnswer>> for Recording {
+    fn from(from: Vec<RecordedAnswer>) -> Self {
+        Self(from)
+    }
+}
+
+impl<C: DnsClient> RecordingDnsClient<C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner, recording: Default::default(), file: None }
+    }
+
+    /// Same as [RecordingDnsClient::new], but also writes each answer to the given file (which
+    /// is truncated first) as soon as it comes in.
+    pub fn with_file(inner: C, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = File::create(path.as_ref()).map_err(|e| {
+            Error::Unexpected(format!("could not create {}: {}", path.as_ref().display(), e))
+        })?;
+
+        Ok(Self { inner, recording: Default::default(), file: Some(Arc::new(Mutex::new(file))) })
+    }
+
+    /// Returns everything recorded so far.
+    pub fn recording(&self) -> Recording {
+        self.recording.lock().expect("recording lock poisoned").clone()
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn record(&self, answer: RecordedAnswer) {
+        if let Some(file) = &self.file {
+            let mut file = file.lock().expect("recording file lock poisoned");
+            //a recording is best effort, it should never fail the lookup itself
+            let _ = write!(file, "{}", Recording::from(vec![answer.clone()]));
+        }
+
+        self.recording.lock().expect("recording lock poisoned").push(answer);
+    }
+}
+
+#[async_trait]
+impl<C: DnsClient> DnsClient for RecordingDnsClient<C> {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        self.try_naptr_lookup(domain).await.ok().flatten()
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        self.try_srv_lookup(domain).await.ok().flatten()
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        self.try_ip_lookup(domain.clone())
+            .await?
+            .ok_or_else(|| Error::Unexpected(format!("no A/AAAA records for {}", domain)))
+    }
+
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        let answer = self.inner.try_naptr_lookup(domain.clone()).await;
+        self.record(RecordedAnswer::Naptr { domain, answer: recorded(&answer) });
+
+        answer
+    }
+
+    async fn try_srv_lookup(&self, domain: SrvDomain) -> Result<Option<SrvRecord>, Error> {
+        let answer = self.inner.try_srv_lookup(domain.clone()).await;
+        self.record(RecordedAnswer::Srv { domain, answer: recorded(&answer) });
+
+        answer
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        let answer = self.inner.try_ip_lookup(domain.clone()).await;
+        self.record(RecordedAnswer::Addr { domain, answer: recorded(&answer) });
+
+        answer
+    }
+
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        let answer = self.inner.try_cname_lookup(domain.clone()).await;
+        self.record(RecordedAnswer::Cname { domain, answer: recorded(&answer) });
+
+        answer
+    }
+}
+
+fn recorded<T: Clone>(answer: &Result<Option<T>, Error>) -> Result<Option<T>, String> {
+    answer.clone().map_err(|e| e.to_string())
+}
+
+impl ReplayDnsClient {
+    pub fn new(recording: Recording) -> Self {
+        let mut answers: HashMap<String, Vec<RecordedAnswer>> = HashMap::new();
+        for answer in recording.0 {
+            answers.entry(answer.key()).or_default().push(answer);
+        }
+
+        Self { answers: Arc::new(Mutex::new(answers)) }
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Self::new(Recording::from_path(path)?))
+    }
+
+    fn next_answer(&self, key: String) -> RecordedAnswer {
+        let mut answers = self.answers.lock().expect("replay lock poisoned");
+
+        match answers.get_mut(&key) {
+            Some(answers) if answers.len() == 1 => answers[0].clone(),
+            Some(answers) => answers.remove(0),
+            None => {
+                //never panic while holding the lock, so that it's not poisoned
+                drop(answers);
+                panic!("query `{}` is not part of the recording", key)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DnsClient for ReplayDnsClient {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        self.try_naptr_lookup(domain).await.ok().flatten()
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        self.try_srv_lookup(domain).await.ok().flatten()
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        self.try_ip_lookup(domain.clone())
+            .await?
+            .ok_or_else(|| Error::Unexpected(format!("no A/AAAA records for {}", domain)))
+    }
+
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        match self.next_answer(format!("naptr {}", key_of(&domain))) {
+            RecordedAnswer::Naptr { answer, .. } => answer.map_err(Error::Unexpected),
+            _ => unreachable!("answers are keyed by query type"),
+        }
+    }
+
+    async fn try_srv_lookup(&self, domain: SrvDomain) -> Result<Option<SrvRecord>, Error> {
+        match self.next_answer(format!("srv {}", key_of(&domain.to_string().into()))) {
+            RecordedAnswer::Srv { answer, .. } => answer.map_err(Error::Unexpected),
+            _ => unreachable!("answers are keyed by query type"),
+        }
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        match self.next_answer(format!("addr {}", key_of(&domain))) {
+            RecordedAnswer::Addr { answer, .. } => answer.map_err(Error::Unexpected),
+            _ => unreachable!("answers are keyed by query type"),
+        }
+    }
+
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        match self.next_answer(format!("cname {}", key_of(&domain))) {
+            RecordedAnswer::Cname { answer, .. } => answer.map_err(Error::Unexpected),
+            _ => unreachable!("answers are keyed by query type"),
+        }
+    }
+}
+
+impl std::fmt::Display for Recording {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for answer in self.0.iter() {
+            let line = serde_json::to_string(answer).map_err(|_| std::fmt::Error)?;
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Recording {
+    type Err = Error;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| Error::ParseError(format!("line {}: {}", index + 1, e)))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
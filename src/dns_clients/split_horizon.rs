@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+
+use super::key_of;
+use crate::{records::*, DnsClient};
+use rsip::{Domain, Error};
+
+/// A [DnsClient] that routes each query to an inner client by the longest matching domain
+/// suffix, falling back to a default client when no suffix matches. For instance, internal
+/// domains (`*.corp.example`) can be resolved by one resolver and everything else by another.
+///
+/// Suffixes match whole labels only (`corp.example` matches `corp.example` and
+/// `sip.corp.example`, but not `notcorp.example`), case insensitively. SRV queries are routed by
+/// their underlying domain, ignoring the service and protocol labels (`_sip._tcp`).
+///
+/// ```
+/// use rsip_dns::{SplitHorizonDnsClient, ZoneFileDnsClient};
+///
+/// let internal = ZoneFileDnsClient::parse("sip.corp.example. A 10.0.0.1").unwrap();
+/// let dns_client = SplitHorizonDnsClient::new(ZoneFileDnsClient::default())
+///     .with_route("corp.example", internal);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SplitHorizonDnsClient<D: DnsClient, R: DnsClient> {
+    default: D,
+    routes: Vec<(String, R)>,
+}
+
+impl<D: DnsClient, R: DnsClient> SplitHorizonDnsClient<D, R> {
+    pub fn new(default: D) -> Self {
+        Self { default, routes: vec![] }
+    }
+
+    /// Routes the queries of the given domain, and any of its subdomains, to the client.
+    pub fn with_route(mut self, suffix: impl Into<Domain>, client: R) -> Self {
+        self.routes.push((key_of(&suffix.into()), client));
+        self
+    }
+
+    /// Returns the routed client of the domain, if any of the suffixes matches it.
+    pub fn route_for(&self, domain: &Domain) -> Option<&R> {
+        let domain = key_of(domain);
+
+        self.routes
+            .iter()
+            .filter(|(suffix, _)| {
+                domain == *suffix
+                    || (domain.ends_with(suffix.as_str())
+                        && domain[..domain.len() - suffix.len()].ends_with('.'))
+            })
+            .max_by_key(|(suffix, _)| suffix.len())
+            .map(|(_, client)| client)
+    }
+}
+
+#[async_trait]
+impl<D: DnsClient, R: DnsClient> DnsClient for SplitHorizonDnsClient<D, R> {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        match self.route_for(&domain) {
+            Some(client) => client.naptr_lookup(domain).await,
+            None => self.default.naptr_lookup(domain).await,
+        }
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        match self.route_for(&domain.domain) {
+            Some(client) => client.srv_lookup(domain).await,
+            None => self.default.srv_lookup(domain).await,
+        }
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        match self.route_for(&domain) {
+            Some(client) => client.ip_lookup(domain).await,
+            None => self.default.ip_lookup(domain).await,
+        }
+    }
+
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        match self.route_for(&domain) {
+            Some(client) => client.try_naptr_lookup(domain).await,
+            None => self.default.try_naptr_lookup(domain).await,
+        }
+    }
+
+    async fn try_srv_lookup(&self, domain: SrvDomain) -> Result<Option<SrvRecord>, Error> {
+        match self.route_for(&domain.domain) {
+            Some(client) => client.try_srv_lookup(domain).await,
+            None => self.default.try_srv_lookup(domain).await,
+        }
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        match self.route_for(&domain) {
+            Some(client) => client.try_ip_lookup(domain).await,
+            None => self.default.try_ip_lookup(domain).await,
+        }
+    }
+
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        match self.route_for(&domain) {
+            Some(client) => client.try_cname_lookup(domain).await,
+            None => self.default.try_cname_lookup(domain).await,
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::Path,
+    sync::Arc,
+};
+
+use super::key_of;
+use crate::{records::*, DnsClient};
+use rsip::{Domain, Error};
+
+/// A [DnsClient] that resolves against the NAPTR, SRV, A, AAAA and CNAME records of an
+/// [RFC 1035](https://datatracker.ietf.org/doc/html/rfc1035#section-5) master (zone) file,
+/// without any network access. Useful for tests and air-gapped setups.
+///
+/// CNAME records are only given by [DnsClient::try_cname_lookup], the other queries don't follow
+/// them.
+///
+/// `$ORIGIN`, `$TTL`, `@`, relative names, omitted owners, `;` comments and multi-line `( )`
+/// records are supported. Records of other types (like SOA or NS) are accepted but ignored, while
+/// `$INCLUDE` and classes other than `IN` are rejected. Any parse error mentions the line
+/// of the offending record.
+///
+/// ```
+/// use rsip_dns::ZoneFileDnsClient;
+///
+/// let dns_client = ZoneFileDnsClient::parse(r#"
+/// $ORIGIN example.com.
+/// $TTL 3600
+/// @                  IN NAPTR 10 10 "S" "SIP+D2T" "" _sip._tcp
+/// _sip._tcp          IN SRV   10 10 5060 sip1
+/// sip1               IN A     10.0.0.1
+/// "#).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ZoneFileDnsClient {
+    zone: Arc<Zone>,
+}
+
+#[derive(Debug, Default)]
+struct Zone {
+    naptr: HashMap<String, NaptrRecord>,
+    srv: HashMap<String, SrvRecord>,
+    addr: HashMap<String, AddrRecord>,
+    cname: HashMap<String, Domain>,
+}
+
+impl ZoneFileDnsClient {
+    /// Parses the contents of a zone file. Relative names are only allowed after an `$ORIGIN`
+    /// directive.
+    pub fn parse(contents: &str) -> Result<Self, Error> {
+        Parser::new(None).parse(contents)
+    }
+
+    /// Same as [ZoneFileDnsClient::parse], but with an initial origin, as if the zone file
+    /// started with `$ORIGIN <origin>`.
+    pub fn parse_with_origin(contents: &str, origin: Domain) -> Result<Self, Error> {
+        Parser::new(Some(key_of(&origin))).parse(contents)
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let contents = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            Error::Unexpected(format!("could not read {}: {}", path.as_ref().display(), e))
+        })?;
+
+        Self::parse(&contents)
+    }
+
+    pub fn naptr_records(&self) -> Vec<NaptrRecord> {
+        self.zone.naptr.values().cloned().collect()
+    }
+
+    pub fn srv_records(&self) -> Vec<SrvRecord> {
+        self.zone.srv.values().cloned().collect()
+    }
+
+    pub fn addr_records(&self) -> Vec<AddrRecord> {
+        self.zone.addr.values().cloned().collect()
+    }
+}
+
+#[async_trait]
+impl DnsClient for ZoneFileDnsClient {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        self.zone.naptr.get(&key_of(&domain)).cloned()
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        self.zone.srv.get(&key_of(&domain.to_string().into())).cloned()
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        self.zone
+            .addr
+            .get(&key_of(&domain))
+            .cloned()
+            .ok_or_else(|| Error::Unexpected(format!("no A/AAAA records for {}", domain)))
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        Ok(self.zone.addr.get(&key_of(&domain)).cloned())
+    }
+
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        Ok(self.zone.cname.get(&key_of(&domain)).cloned())
+    }
+}
+
+//a record or a directive, possibly spanning multiple lines using parentheses
+#[derive(Debug)]
+struct Entry {
+    line: usize,
+    owner_omitted: bool,
+    tokens: Vec<String>,
+}
+
+struct Parser {
+    origin: Option<String>,
+    last_owner: Option<String>,
+    zone: Zone,
+}
+
+impl Parser {
+    fn new(origin: Option<String>) -> Self {
+        Self { origin, last_owner: None, zone: Zone::default() }
+    }
+
+    fn parse(mut self, contents: &str) -> Result<ZoneFileDnsClient, Error> {
+        for entry in entries_of(contents)? {
+            self.parse_entry(&entry).map_err(|e| parse_error(entry.line, e))?;
+        }
+
+        Ok(ZoneFileDnsClient { zone: Arc::new(self.zone) })
+    }
+
+    fn parse_entry(&mut self, entry: &Entry) -> Result<(), String> {
+        let tokens = entry.tokens.as_slice();
+
+        if !entry.owner_omitted && tokens[0].starts_with('$') {
+            return self.parse_directive(&tokens[0], &tokens[1..]);
+        }
+
+        let (owner, mut index) = match entry.owner_omitted {
+            true => (self.last_owner.clone().ok_or("record without owner")?, 0),
+            false => (self.absolute(&tokens[0])?, 1),
+        };
+        self.last_owner = Some(owner.clone());
+
+        //TTL and class are both optional and can come in any order
+        let record_type = loop {
+            match tokens.get(index).map(String::as_str) {
+                Some(token) if token.starts_with(|c: char| c.is_ascii_digit()) => {
+                    ttl_from(token)?;
+                }
+                Some(token) if token.eq_ignore_ascii_case("IN") => (),
+                Some(token) if ["CH", "HS", "CS"].iter().any(|c| token.eq_ignore_ascii_case(c)) => {
+                    return Err(format!("unsupported class {}", token))
+                }
+                Some(token) => break token.to_ascii_uppercase(),
+                None => return Err("missing record type".into()),
+            }
+            index += 1;
+        };
+        let rdata = &tokens[index + 1..];
+
+        match record_type.as_str() {
+            "A" => {
+                let ip_addr = single(rdata, "A")?
+                    .parse::<Ipv4Addr>()
+                    .map_err(|_| "invalid IPv4 address in A record")?;
+                self.push_addr(owner, ip_addr.into());
+            }
+            "AAAA" => {
+                let ip_addr = single(rdata, "AAAA")?
+                    .parse::<Ipv6Addr>()
+                    .map_err(|_| "invalid IPv6 address in AAAA record")?;
+                self.push_addr(owner, ip_addr.into());
+            }
+            "CNAME" => {
+                let target = self.absolute(single(rdata, "CNAME")?)?;
+                if self.zone.cname.insert(owner.clone(), target.into()).is_some() {
+                    return Err(format!("{} has more than one CNAME record", owner));
+                }
+            }
+            "SRV" => self.push_srv(owner, rdata)?,
+            "NAPTR" => self.push_naptr(owner, rdata)?,
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn parse_directive(&mut self, directive: &str, args: &[String]) -> Result<(), String> {
+        match (directive.to_ascii_uppercase().as_str(), args) {
+            ("$ORIGIN", [origin]) => self.origin = Some(self.absolute(origin)?),
+            ("$TTL", [ttl]) => {
+                ttl_from(ttl)?;
+            }
+            ("$ORIGIN", _) | ("$TTL", _) => return Err(format!("invalid {} directive", directive)),
+            ("$INCLUDE", _) => return Err("$INCLUDE is not supported".into()),
+            (directive, _) => return Err(format!("unknown directive {}", directive)),
+        }
+
+        Ok(())
+    }
+
+    //returns the absolute name (lowercase, without the trailing dot) of the given name
+    fn absolute(&self, name: &str) -> Result<String, String> {
+        let name = name.to_ascii_lowercase();
+
+        match (name.as_str(), &self.origin) {
+            (".", _) => Ok(".".into()),
+            ("@", Some(origin)) => Ok(origin.clone()),
+            (name, _) if name.ends_with('.') => Ok(name.trim_end_matches('.').into()),
+            (name, Some(origin)) => Ok(format!("{}.{}", name, origin)),
+            (name, None) => Err(format!("relative name {} without $ORIGIN", name)),
+        }
+    }
+
+    fn push_addr(&mut self, owner: String, ip_addr: IpAddr) {
+        self.zone
+            .addr
+            .entry(owner.clone())
+            .or_insert_with(|| AddrRecord { domain: owner.into(), ip_addrs: vec![] })
+            .ip_addrs
+            .push(ip_addr);
+    }
+
+    fn push_srv(&mut self, owner: String, rdata: &[String]) -> Result<(), String> {
+        let (priority, weight, port, target) = match rdata {
+            [priority, weight, port, target] => (
+                number_from(priority, "SRV priority")?,
+                number_from(weight, "SRV weight")?,
+                number_from(port, "SRV port")?,
+                self.absolute(target)?,
+            ),
+            _ => return Err("SRV record expects priority, weight, port and target".into()),
+        };
+        let domain = SrvDomain::try_from(owner.as_str())
+            .map_err(|_| format!("{} is not a valid SRV owner", owner))?;
+
+        self.zone
+            .srv
+            .entry(owner)
+            .or_insert_with(|| SrvRecord { domain, entries: vec![], additional_addrs: vec![] })
+            .entries
+            .push(SrvEntry { priority, weight, port: port.into(), target: target.into() });
+
+        Ok(())
+    }
+
+    fn push_naptr(&mut self, owner: String, rdata: &[String]) -> Result<(), String> {
+        let entry = match rdata {
+            [order, preference, flags, services, regexp, replacement] => NaptrEntry {
+                order: number_from(order, "NAPTR order")?,
+                preference: number_from(preference, "NAPTR preference")?,
+                flags: flags.as_bytes().into(),
+                services: NaptrServices::try_from(services.as_bytes())
+                    .unwrap_or_else(|_| NaptrServices::Other(services.clone())),
+                regexp: regexp.as_bytes().to_vec(),
+                replacement: self.absolute(replacement)?.into(),
+            },
+            _ => return Err(
+                "NAPTR record expects order, preference, flags, services, regexp and replacement"
+                    .into(),
+            ),
+        };
+
+        self.zone
+            .naptr
+            .entry(owner.clone())
+            .or_insert_with(|| NaptrRecord {
+                domain: owner.into(),
+                entries: vec![],
+                additional_srvs: vec![],
+            })
+            .entries
+            .push(entry);
+
+        Ok(())
+    }
+}
+
+fn parse_error(line: usize, message: String) -> Error {
+    Error::ParseError(format!("line {}: {}", line, message))
+}
+
+fn single<'a>(rdata: &'a [String], record_type: &str) -> Result<&'a str, String> {
+    match (rdata, record_type) {
+        ([token], _) => Ok(token.as_str()),
+        (_, "CNAME") => Err("CNAME record expects a single name".into()),
+        _ => Err(format!("{} record expects a single address", record_type)),
+    }
+}
+
+fn number_from(token: &str, field: &str) -> Result<u16, String> {
+    token.parse::<u16>().map_err(|_| format!("invalid {} {}", field, token))
+}
+
+//accepts plain seconds or BIND style units, like 1h30m
+fn ttl_from(token: &str) -> Result<u32, String> {
+    let invalid = || format!("invalid TTL {}", token);
+    let mut ttl: u32 = 0;
+    let mut number = String::new();
+
+    for c in token.chars() {
+        let multiplier = match c.to_ascii_lowercase() {
+            c if c.is_ascii_digit() => {
+                number.push(c);
+                continue;
+            }
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let value = number.parse::<u32>().map_err(|_| invalid())?;
+        ttl = value.checked_mul(multiplier).and_then(|v| ttl.checked_add(v)).ok_or_else(invalid)?;
+        number.clear();
+    }
+
+    match number.is_empty() {
+        true => Ok(ttl),
+        false => number.parse::<u32>().ok().and_then(|v| ttl.checked_add(v)).ok_or_else(invalid),
+    }
+}
+
+//splits the contents into entries, removing comments and joining parenthesized lines
+fn entries_of(contents: &str) -> Result<Vec<Entry>, Error> {
+    let mut entries = vec![];
+    let mut current: Option<Entry> = None;
+    let mut depth = 0;
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let entry = current.get_or_insert_with(|| Entry {
+            line: line_number,
+            owner_omitted: line.starts_with([' ', '\t']),
+            tokens: vec![],
+        });
+
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                ';' => break,
+                ' ' | '\t' => continue,
+                '(' => depth += 1,
+                ')' if depth == 0 => return Err(parse_error(line_number, "unbalanced )".into())),
+                ')' => depth -= 1,
+                '"' => {
+                    let mut text = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => text.extend(chars.next()),
+                            Some(c) => text.push(c),
+                            None => {
+                                return Err(parse_error(line_number, "unterminated string".into()))
+                            }
+                        }
+                    }
+                    entry.tokens.push(text);
+                }
+                c => {
+                    let mut text = c.to_string();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || c == ';' || c == '(' || c == ')' || c == '"' {
+                            break;
+                        }
+                        text.push(c);
+                        chars.next();
+                    }
+                    entry.tokens.push(text);
+                }
+            }
+        }
+
+        if depth == 0 {
+            entries.extend(current.take().filter(|entry| !entry.tokens.is_empty()));
+        }
+    }
+
+    match current {
+        Some(entry) if depth > 0 => Err(parse_error(entry.line, "unbalanced (".into())),
+        _ => Ok(entries),
+    }
+}
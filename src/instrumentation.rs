@@ -0,0 +1,127 @@
+//! Tracing instrumentation, behind the `tracing` feature.
+//!
+//! Every [Lookup](crate::Lookup) opens a `lookup` span and every query it sends opens a
+//! `dns_query` span inside it, that records the record type, the domain, the number of answers,
+//! the latency (in milliseconds) and the outcome of the query. The [TraceEvent]s of the lookup
+//! are emitted as events of the lookup span, along with an event whenever a SRV or an A/AAAA
+//! fallback branch starts.
+
+use crate::{observer::Answer, Context, DnsClient, QueryOutcome, QueryType, TraceEvent};
+use std::time::Duration;
+use tracing::{field::Empty, Span};
+
+pub(crate) fn lookup_span<C: DnsClient>(ctx: &Context<C>) -> Span {
+    let span = tracing::info_span!(
+        "lookup",
+        host = %ctx.host,
+        port = Empty,
+        transport = Empty,
+        stop_reason = Empty,
+    );
+    if let Some(port) = ctx.port {
+        span.record("port", &u16::from(port));
+    }
+    if let Some(transport) = ctx.transport {
+        span.record("transport", &tracing::field::display(transport));
+    }
+
+    span
+}
+
+//the parent is the lookup span, or the current span for resolvables used without a lookup
+pub(crate) fn query_span(parent: Option<Span>, query_type: QueryType, domain: &str) -> Span {
+    tracing::info_span!(
+        parent: parent.unwrap_or_else(Span::current),
+        "dns_query",
+        record_type = ?query_type,
+        domain = %domain,
+        answers = Empty,
+        latency_ms = Empty,
+        outcome = Empty,
+    )
+}
+
+pub(crate) fn record_answer<A: Answer>(span: &Span, answer: &A, latency: Duration) {
+    let outcome = answer.outcome();
+
+    span.record("latency_ms", &(latency.as_secs_f64() * 1000.0));
+    span.record("outcome", &outcome.as_str());
+    match outcome {
+        QueryOutcome::Answered(records) => {
+            span.record("answers", &(records as u64));
+        }
+        QueryOutcome::Empty => {
+            span.record("answers", &0u64);
+        }
+        _ => (),
+    }
+    if let Some(error) = answer.error() {
+        tracing::debug!(parent: span, %error, "query failed");
+    }
+}
+
+pub(crate) fn record_deadline_exceeded(span: &Span) {
+    span.record("outcome", &QueryOutcome::DeadlineExceeded.as_str());
+}
+
+//the fallback branches of a lookup, see PlannedStep::Srv and PlannedStep::AddrFallback
+pub(crate) fn srv_branch_started(span: Option<Span>, domain: &crate::SrvDomain) {
+    tracing::info!(parent: span.unwrap_or_else(Span::current), %domain, "SRV branch started");
+}
+
+pub(crate) fn addr_fallback_started(
+    span: Option<Span>,
+    domain: &rsip::Domain,
+    transport: rsip::Transport,
+) {
+    tracing::info!(
+        parent: span.unwrap_or_else(Span::current),
+        %domain,
+        %transport,
+        "A/AAAA fallback started"
+    );
+}
+
+pub(crate) fn on_event(span: Option<Span>, event: &TraceEvent) {
+    let span = span.unwrap_or_else(Span::current);
+
+    match event {
+        TraceEvent::NaptrEntrySkipped { entry, reason } => tracing::debug!(
+            parent: &span,
+            services = %entry.services,
+            replacement = %entry.replacement,
+            ?reason,
+            "NAPTR entry skipped"
+        ),
+        TraceEvent::SrvEntrySkipped { entry, reason } => {
+            tracing::debug!(parent: &span, target = %entry.target, ?reason, "SRV entry skipped")
+        }
+        TraceEvent::SrvPreresolved(record) => {
+            tracing::debug!(parent: &span, domain = %record.domain, "SRV record preresolved")
+        }
+        TraceEvent::AddrPreresolved(record) => {
+            tracing::debug!(parent: &span, domain = %record.domain, "A/AAAA record preresolved")
+        }
+        TraceEvent::SrvUnavailable(domain) => {
+            tracing::debug!(parent: &span, %domain, "SRV service not available")
+        }
+        TraceEvent::AddrFallbackSkipped { domain, transport } => {
+            tracing::debug!(parent: &span, %domain, %transport, "A/AAAA fallback skipped")
+        }
+        TraceEvent::Target(target) => {
+            tracing::debug!(
+                parent: &span,
+                ip_addr = %target.ip_addr,
+                port = u16::from(target.port),
+                transport = %target.transport,
+                "target yielded"
+            );
+        }
+        TraceEvent::Stopped(reason) => {
+            span.record("stop_reason", &tracing::field::debug(reason));
+            tracing::debug!(parent: &span, ?reason, "lookup stopped");
+        }
+        //queries and answers have their own span
+        _ => (),
+    }
+}
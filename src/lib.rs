@@ -38,14 +38,10 @@
 //! # }
 //! # let my_dns_client = CustomDnsClient;
 //!
-//! let context = Context {
-//!     secure: true,
-//!     host: Host::from(IpAddr::V4(Ipv4Addr::new(192, 168, 2, 13))),
-//!     transport: Some(Transport::Udp),
-//!     port: Some(Port::from(5060)),
-//!     dns_client: my_dns_client,
-//!     supported_transports: Default::default()
-//! };
+//! let context = Context::new(Host::from(IpAddr::V4(Ipv4Addr::new(192, 168, 2, 13))), my_dns_client)
+//!     .with_secure(true)
+//!     .with_transport(Transport::Udp)
+//!     .with_port(Port::from(5060));
 //!```
 //!
 //! Here we created a context rather manually, but you can create a context out of a url as well
@@ -139,7 +135,9 @@
 //!             ip_addr,
 //!             port,
 //!             transport,
-//!         }) => println!("next tuple: ({:?}, {:?}, {:?})", ip_addr, port, transport),
+//!             provenance,
+//!             ..
+//!         }) => println!("next tuple: ({:?}, {:?}, {:?}) from {}", ip_addr, port, transport, provenance),
 //!         None => break,
 //!     }
 //! }
@@ -181,6 +179,8 @@
 //!          * for each address record found, use (ip, srv port, given transport)
 //!  * if no SRV records are found **perform** an A or AAAA and to get the ip addrs
 //!      * use the default Port for the given transport and try each (ip, default port, given transport)
+//!  * if the SRV lookup returns a single record with a `.` target, the service is not available at
+//!    that domain: nothing is returned for it and the A or AAAA fallback for the same transport is skipped
 //!
 //! ##### 4. Domain without Port or Transport
 //!  * **perform** a NAPTR query to get all replacemenets domains
@@ -206,21 +206,67 @@
 //! The structure of the code follows this pattern by defining a `ResolvableExt` trait,
 //! `Resolvable` type and other types that are built on top of `Resolvable` or implement
 //! `ResolvableExt` trait.
+//!
+//! ## Serde
+//! Under the `serde` feature flag, [Target], [SupportedTransports] and the DNS records implement
+//! `Serialize` and `Deserialize`, using a human-readable representation: domains, transports,
+//! NAPTR services and SRV domains are plain strings (like `"TLS"` or
+//! `"_sips._tcp.example.com"`), ports are numbers. A [Recording] of the answers of a
+//! [RecordingDnsClient], replayed by a [ReplayDnsClient], is built on the same representation.
+//!
+//! ## Tracing
+//! Under the `tracing` feature flag, every [Lookup] opens a `lookup` span and every NAPTR, SRV and
+//! A/AAAA query a `dns_query` span inside it, with the record type, domain, answer count, latency
+//! and outcome of the query. Skipped entries, yielded targets and the start of the SRV and A/AAAA
+//! fallback branches are emitted as events of the lookup span. The lookup span is a child of the
+//! span that is current when the [Lookup] is created, like the span of a SIP transaction.
+//!
+//! ## Metrics
+//! An [Observer] set with [Lookup::with_observer] is called at key points of the lookup, like
+//! when a query starts and ends, a target is yielded or a branch leads to nothing. Under the
+//! `metrics` feature flag, the `MetricsObserver` records them through the
+//! [metrics](https://docs.rs/metrics) facade.
 
 mod context;
 mod dns_client;
+mod dns_clients;
+#[cfg(feature = "tracing")]
+mod instrumentation;
+mod linter;
 mod lookup;
+#[cfg(feature = "metrics")]
+mod metrics_observer;
+mod observer;
+#[cfg(feature = "serde")]
+mod serialization;
 mod target;
+mod target_memo;
+mod tracker;
+mod zone_generator;
 
 pub mod records;
 pub mod resolvables;
 
-pub use context::{Context, SupportedTransports};
+pub use context::{Context, Limits, SupportedTransports};
 pub use dns_client::DnsClient;
-pub use lookup::Lookup;
-pub use records::SrvDomain;
-pub use resolvables::ResolvableExt;
-pub use target::Target;
+pub use dns_clients::{
+    FallbackDnsClient, OverrideDnsClient, QueryType, SplitHorizonDnsClient, ZoneFileDnsClient,
+};
+#[cfg(feature = "test-utils")]
+pub use dns_clients::{Fault, FaultyDnsClient, PanicDnsClient, SpyDnsClient, StaticDnsClient};
+#[cfg(feature = "serde")]
+pub use dns_clients::{RecordedAnswer, Recording, RecordingDnsClient, ReplayDnsClient};
+pub use linter::{Linter, Problem, Report, Severity};
+pub use lookup::{Lookup, Plan, PlannedStep};
+#[cfg(feature = "metrics")]
+pub use metrics_observer::MetricsObserver;
+pub use observer::{Observer, QueryOutcome};
+pub use records::{SrvDomain, SrvService};
+pub use resolvables::{ResolvableExt, SrvSelection};
+pub use target::{Provenance, ProvenanceStep, Target};
+pub use target_memo::{TargetMemo, TransactionKey};
+pub use tracker::{SkipReason, StopReason, TraceEvent, Tracker};
+pub use zone_generator::{GeneratedZone, Node, ZoneGenerator};
 
 #[cfg(feature = "trust-dns")]
 mod trust_dns;
@@ -228,6 +274,8 @@ mod trust_dns;
 pub use trust_dns::AsyncTrustDnsClient;
 #[cfg(feature = "trust-dns")]
 pub use trust_dns::TrustDnsClient;
+#[cfg(all(feature = "trust-dns", feature = "test-utils"))]
+pub use trust_dns::{TestDnsServer, TestZone};
 #[cfg(feature = "trust-dns")]
 pub use trust_dns_proto;
 #[cfg(feature = "trust-dns")]
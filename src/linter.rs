@@ -0,0 +1,320 @@
+use crate::{
+    records::{same_domain, NaptrEntry, NaptrFlags, SrvDomain, SrvRecord, SrvService},
+    DnsClient, QueryType, SupportedTransports,
+};
+use rsip::{Domain, Transport};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+};
+
+/// Inspects the SIP related DNS records of a domain, through any [DnsClient], and reports
+/// misconfigurations that would make RFC 3263 lookups fail (or behave unexpectedly), like NAPTR
+/// replacements without SRV records or SRV targets without addresses.
+///
+/// The NAPTR records are checked first. If there are none, the SRV records of every supported
+/// transport are checked instead and, if there are none either, the A/AAAA records of the domain.
+///
+/// SRV targets that are aliases can only be found if the client implements
+/// [DnsClient::try_cname_lookup]. TTLs are not exposed by the [DnsClient], hence mismatched TTLs
+/// are not checked at all.
+///
+/// ```
+/// # use rsip_dns::*;
+/// # async fn lint(dns_client: impl DnsClient) {
+/// let report = Linter::new(dns_client).lint("example.com".into()).await;
+///
+/// for problem in report.problems.iter() {
+///     println!("{:?}: {}", problem.severity(), problem);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Linter<C: DnsClient> {
+    dns_client: C,
+    supported_transports: SupportedTransports,
+}
+
+/// The outcome of [Linter::lint]: the problems found, in the order they were found.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Report {
+    pub domain: Domain,
+    pub problems: Vec<Problem>,
+}
+
+/// How serious a [Problem] is: errors break (part of) the resolution, warnings are likely
+/// mistakes that don't.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A misconfiguration found by the [Linter].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Problem {
+    /// A query failed, as opposed to being answered without records.
+    QueryFailed { query_type: QueryType, name: String, error: String },
+    /// There are no NAPTR, SRV or A/AAAA records at all.
+    NoRecords(Domain),
+    /// The NAPTR flags are other than `S`, hence RFC 3263 clients ignore the entry.
+    UnsupportedNaptrFlags(NaptrEntry),
+    /// The NAPTR service is unknown or its transport is not among the supported transports.
+    UnsupportedNaptrService(NaptrEntry),
+    /// The NAPTR replacement is not a SRV domain (`_service._proto.name`).
+    InvalidNaptrReplacement(NaptrEntry),
+    /// The NAPTR service doesn't match the service or protocol of its replacement, like a
+    /// `SIPS+D2T` entry pointing to `_sip._udp.example.com`.
+    NaptrReplacementMismatch { entry: NaptrEntry, replacement: SrvDomain },
+    /// The NAPTR replacement has no SRV records.
+    MissingSrv { entry: NaptrEntry, replacement: SrvDomain },
+    /// A SIPS NAPTR service is advertised, but its TLS replacement has no SRV records.
+    SipsWithoutTlsSrv { entry: NaptrEntry, replacement: SrvDomain },
+    /// There are no NAPTR records and the domain has SIP SRV records, but not TLS ones (the given
+    /// `_sips._tcp` domain), hence SIPS clients fall back to its A/AAAA records.
+    MissingTlsSrv(SrvDomain),
+    /// The SRV target has no A/AAAA records.
+    SrvTargetWithoutAddress { domain: SrvDomain, target: Domain },
+    /// The SRV target is an alias (it has a CNAME record), which
+    /// [RFC 2782](https://datatracker.ietf.org/doc/html/rfc2782) forbids.
+    SrvTargetIsAlias { domain: SrvDomain, target: Domain, canonical: Domain },
+}
+
+//the state of a single lint run, so that each name is queried (and reported) once
+#[derive(Debug, Default)]
+struct Run {
+    problems: Vec<Problem>,
+    srvs: HashMap<String, Option<bool>>,
+    targets: HashSet<String>,
+}
+
+impl<C: DnsClient> Linter<C> {
+    pub fn new(dns_client: C) -> Self {
+        Self { dns_client, supported_transports: Default::default() }
+    }
+
+    /// Sets the transports that the SIP clients of the domain support, any NAPTR service of
+    /// another transport is reported. All transports by default.
+    pub fn with_supported_transports(mut self, supported_transports: SupportedTransports) -> Self {
+        self.supported_transports = supported_transports;
+        self
+    }
+
+    pub async fn lint(&self, domain: Domain) -> Report {
+        let mut run = Run::default();
+
+        let naptr_record = match self.dns_client.try_naptr_lookup(domain.clone()).await {
+            Ok(naptr_record) => naptr_record,
+            Err(error) => {
+                run.failed(QueryType::Naptr, domain.to_string(), error);
+                None
+            }
+        };
+
+        match naptr_record {
+            Some(naptr_record) if !naptr_record.entries.is_empty() => {
+                for entry in naptr_record.entries {
+                    self.lint_naptr_entry(entry, &mut run).await;
+                }
+            }
+            _ => self.lint_without_naptr(&domain, &mut run).await,
+        }
+
+        Report { domain, problems: run.problems }
+    }
+
+    async fn lint_naptr_entry(&self, entry: NaptrEntry, run: &mut Run) {
+        if entry.flags != NaptrFlags::S {
+            return run.problems.push(Problem::UnsupportedNaptrFlags(entry));
+        }
+        let transport = match entry.services.transport() {
+            Some(transport) if self.supported_transports.all().contains(&transport) => transport,
+            _ => return run.problems.push(Problem::UnsupportedNaptrService(entry)),
+        };
+        let replacement = match SrvDomain::try_from(entry.replacement.clone()) {
+            Ok(replacement) => replacement,
+            Err(_) => return run.problems.push(Problem::InvalidNaptrReplacement(entry)),
+        };
+
+        if replacement.service != SrvService::Sip || replacement.transport() != transport {
+            run.problems.push(Problem::NaptrReplacementMismatch {
+                entry: entry.clone(),
+                replacement: replacement.clone(),
+            });
+        }
+
+        if self.lint_srv(&replacement, run).await == Some(false) {
+            run.problems.push(match entry.services.secure() {
+                true => Problem::SipsWithoutTlsSrv { entry, replacement },
+                false => Problem::MissingSrv { entry, replacement },
+            });
+        }
+    }
+
+    async fn lint_without_naptr(&self, domain: &Domain, run: &mut Run) {
+        let mut found = false;
+        let mut tls_srv = None;
+        for transport in self.supported_transports.all() {
+            let srv_domain = SrvDomain::from((SrvService::Sip, domain.clone(), *transport));
+            let exists = self.lint_srv(&srv_domain, run).await;
+            if *transport == Transport::Tls && exists == Some(false) {
+                tls_srv = Some(srv_domain);
+            }
+            found |= exists.unwrap_or(true);
+        }
+        if found {
+            return run.problems.extend(tls_srv.map(Problem::MissingTlsSrv));
+        }
+
+        match self.dns_client.try_ip_lookup(domain.clone()).await {
+            Ok(Some(addr_record)) if !addr_record.ip_addrs.is_empty() => (),
+            Ok(_) => run.problems.push(Problem::NoRecords(domain.clone())),
+            Err(error) => run.failed(QueryType::Addr, domain.to_string(), error),
+        }
+    }
+
+    //returns whether the SRV records exist, None if that's unknown (the query failed)
+    async fn lint_srv(&self, srv_domain: &SrvDomain, run: &mut Run) -> Option<bool> {
+        let key = srv_domain.to_string().to_ascii_lowercase();
+        if let Some(exists) = run.srvs.get(&key) {
+            return *exists;
+        }
+
+        let exists = match self.dns_client.try_srv_lookup(srv_domain.clone()).await {
+            Ok(Some(srv_record)) if !srv_record.entries.is_empty() => {
+                self.lint_srv_targets(&srv_record, run).await;
+                Some(true)
+            }
+            Ok(_) => Some(false),
+            Err(error) => {
+                run.failed(QueryType::Srv, srv_domain.to_string(), error);
+                None
+            }
+        };
+        run.srvs.insert(key, exists);
+
+        exists
+    }
+
+    async fn lint_srv_targets(&self, srv_record: &SrvRecord, run: &mut Run) {
+        if srv_record.is_unavailable() {
+            return;
+        }
+
+        for entry in srv_record.entries.iter().filter(|entry| !entry.has_root_target()) {
+            let target = &entry.target;
+            if !run.targets.insert(target.to_string().trim_end_matches('.').to_ascii_lowercase()) {
+                continue;
+            }
+
+            match self.dns_client.try_cname_lookup(target.clone()).await {
+                Ok(Some(canonical)) if !same_domain(&canonical, target) => {
+                    run.problems.push(Problem::SrvTargetIsAlias {
+                        domain: srv_record.domain.clone(),
+                        target: target.clone(),
+                        canonical,
+                    })
+                }
+                Ok(_) => (),
+                Err(error) => run.failed(QueryType::Cname, target.to_string(), error),
+            }
+
+            //the addresses that came along with the answer are enough
+            let preresolved = srv_record.addr_record_for(target);
+            if preresolved.filter(|addrs| !addrs.ip_addrs.is_empty()).is_some() {
+                continue;
+            }
+            match self.dns_client.try_ip_lookup(target.clone()).await {
+                Ok(Some(addr_record)) if !addr_record.ip_addrs.is_empty() => (),
+                Ok(_) => run.problems.push(Problem::SrvTargetWithoutAddress {
+                    domain: srv_record.domain.clone(),
+                    target: target.clone(),
+                }),
+                Err(error) => run.failed(QueryType::Addr, target.to_string(), error),
+            }
+        }
+    }
+}
+
+impl Run {
+    fn failed(&mut self, query_type: QueryType, name: String, error: rsip::Error) {
+        self.problems.push(Problem::QueryFailed { query_type, name, error: error.to_string() })
+    }
+}
+
+impl Report {
+    pub fn has_errors(&self) -> bool {
+        self.problems.iter().any(|problem| problem.severity() == Severity::Error)
+    }
+}
+
+impl Problem {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::UnsupportedNaptrFlags(_)
+            | Self::UnsupportedNaptrService(_)
+            | Self::NaptrReplacementMismatch { .. }
+            | Self::MissingTlsSrv(_)
+            | Self::SrvTargetIsAlias { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::QueryFailed { query_type, name, error } => {
+                write!(f, "{:?} query for {} failed: {}", query_type, name, error)
+            }
+            Self::NoRecords(domain) => write!(f, "no NAPTR, SRV or A/AAAA records for {}", domain),
+            Self::UnsupportedNaptrFlags(entry) => write!(
+                f,
+                "NAPTR {} {} has flags {}, only S is supported",
+                entry.services,
+                entry.replacement,
+                String::from_utf8_lossy(entry.flags.as_bytes())
+            ),
+            Self::UnsupportedNaptrService(entry) => {
+                write!(f, "NAPTR service {} is not supported", entry.services)
+            }
+            Self::InvalidNaptrReplacement(entry) => write!(
+                f,
+                "NAPTR {} replacement {} is not a SRV domain",
+                entry.services, entry.replacement
+            ),
+            Self::NaptrReplacementMismatch { entry, replacement } => write!(
+                f,
+                "NAPTR {} points to {}, which is meant for {}",
+                entry.services,
+                replacement,
+                replacement.transport()
+            ),
+            Self::MissingSrv { entry, replacement } => {
+                write!(
+                    f,
+                    "NAPTR {} points to {}, which has no SRV records",
+                    entry.services, replacement
+                )
+            }
+            Self::SipsWithoutTlsSrv { entry, replacement } => write!(
+                f,
+                "NAPTR advertises {}, but {} has no SRV records",
+                entry.services, replacement
+            ),
+            Self::MissingTlsSrv(domain) => write!(
+                f,
+                "{} has no SRV records (and there is no NAPTR), SIPS clients fall back to A/AAAA",
+                domain
+            ),
+            Self::SrvTargetWithoutAddress { domain, target } => {
+                write!(f, "SRV {} target {} has no A/AAAA records", domain, target)
+            }
+            Self::SrvTargetIsAlias { domain, target, canonical } => write!(
+                f,
+                "SRV {} target {} is an alias of {} (CNAME), SRV targets must be canonical names",
+                domain, target, canonical
+            ),
+        }
+    }
+}
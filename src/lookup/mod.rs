@@ -1,52 +1,168 @@
-use crate::{records::SrvDomain, resolvables::*, Context, DnsClient, Target};
+mod plan;
+
+pub use plan::{Plan, PlannedStep};
+
+use crate::{
+    resolvables::*, Context, DnsClient, Observer, ProvenanceStep, StopReason, Target, TraceEvent,
+    Tracker,
+};
 use async_trait::async_trait;
 use rsip::{Domain, Host, Port, Transport};
-use std::net::IpAddr;
+use std::{net::IpAddr, sync::Arc, time::Instant};
 
+/// Each variant holds the resolvable tree of the lookup along with the [Tracker] that is shared
+/// among all the resolvables of the tree.
+///
+/// The [DnsClient] has to be `'static`, see the [DnsClient] docs.
 #[derive(Debug, Clone)]
 pub enum Lookup<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
-    IpAddr(ResolvableIpAddr),
-    DomainWithPort(ResolvableAddrRecord<C>),
+    IpAddr(ResolvableIpAddr, Tracker),
+    DomainWithPort(ResolvableAddrRecord<C>, Tracker),
     //This variant uses only the given transport as RFC says, but I have a feeling that we should
     //add an exhaustive variant that apart from the given transport, tries AddrRecords for the given
     //available transports.
-    DomainWithTransport(ResolvableVec<ResolvableEnum<C>, Target>),
-    JustDomain(ResolvableVec<ResolvableEnum<C>, Target>),
+    DomainWithTransport(ResolvableVec<ResolvableEnum<C>, Target>, Tracker),
+    JustDomain(ResolvableVec<ResolvableEnum<C>, Target>, Tracker),
+}
+
+impl<C> Lookup<C>
+where
+    C: DnsClient + 'static,
+{
+    /// Returns the [Tracker] that is shared among all the resolvables of this lookup.
+    pub fn tracker(&self) -> &Tracker {
+        match self {
+            Self::IpAddr(_, tracker) => tracker,
+            Self::DomainWithPort(_, tracker) => tracker,
+            Self::DomainWithTransport(_, tracker) => tracker,
+            Self::JustDomain(_, tracker) => tracker,
+        }
+    }
+
+    /// Sets the [Observer] that the lookup and its resolvables call at key points, like when a
+    /// query starts and ends or when a target is yielded.
+    pub fn with_observer(self, observer: Arc<dyn Observer>) -> Self {
+        self.tracker().set_observer(observer);
+        self
+    }
+
+    /// Reports that the last target yielded worked, so that the [Observer] learns how many
+    /// targets were tried before one succeeded.
+    pub fn report_success(&self) {
+        self.tracker().target_succeeded()
+    }
+
+    /// Returns the trace of the lookup so far: every query sent along with its answer, every
+    /// branch that was skipped (and why) and every [Target] yielded. Each yielded [Target] also
+    /// carries its own [Provenance](crate::Provenance).
+    pub fn trace(&self) -> Vec<TraceEvent> {
+        self.tracker().events()
+    }
+
+    /// Resolves all the (remaining) targets of the lookup at once, running up to
+    /// `max_concurrency` queries in parallel: every SRV and A/AAAA query that doesn't depend on
+    /// the answer of another is sent right away, instead of when its branch is reached. The
+    /// targets are given in the same order repeated [resolve_next](ResolvableExt::resolve_next)
+    /// calls would give them, and the [Limits](crate::Limits) of the [Context] still apply.
+    ///
+    /// NAPTR queries and A/AAAA fallbacks are still sent in order, since they depend on the
+    /// outcome of the previous branches. Branches already opened by previous
+    /// [resolve_next](ResolvableExt::resolve_next) calls keep the prefetch window of the
+    /// [Context].
+    ///
+    /// The prefetch window and the concurrency cap are restored once all targets are resolved.
+    /// If the returned future is dropped before that, the lookup keeps them.
+    pub async fn resolve_all(&mut self, max_concurrency: usize) -> Vec<Target> {
+        let (max_in_flight, prefetch) = (self.tracker().max_in_flight(), self.tracker().prefetch());
+        self.tracker().set_max_in_flight(Some(max_concurrency.max(1)));
+        self.tracker().set_prefetch(usize::MAX);
+        let window = self.set_window(usize::MAX);
+
+        let mut targets = vec![];
+        while let Some(target) = self.resolve_next().await {
+            targets.push(target);
+        }
+
+        self.tracker().set_max_in_flight(max_in_flight);
+        self.tracker().set_prefetch(prefetch);
+        self.set_window(window);
+
+        targets
+    }
+
+    //sets the prefetch window of the top level branches, returning the previous one
+    fn set_window(&mut self, window: usize) -> usize {
+        match self {
+            Self::DomainWithTransport(inner, _) | Self::JustDomain(inner, _) => {
+                let previous = inner.window();
+                *inner = std::mem::take(inner).with_prefetch(window);
+                previous
+            }
+            Self::IpAddr(..) | Self::DomainWithPort(..) => 0,
+        }
+    }
+
+    /// Returns why the lookup stopped sending queries before trying every branch, if it reached
+    /// one of the [Limits](crate::Limits) of its [Context].
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.tracker().stop_reason()
+    }
 }
 
 #[async_trait]
 impl<C> ResolvableExt<Target> for Lookup<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     fn state(&self) -> ResolvableState {
         match self {
-            Self::IpAddr(inner) => inner.state(),
-            Self::DomainWithPort(inner) => inner.state(),
-            Self::DomainWithTransport(inner) => inner.state(),
-            Self::JustDomain(inner) => inner.state(),
+            Self::IpAddr(inner, _) => inner.state(),
+            Self::DomainWithPort(inner, _) => inner.state(),
+            Self::DomainWithTransport(inner, _) => inner.state(),
+            Self::JustDomain(inner, _) => inner.state(),
         }
     }
 
     async fn resolve_next(&mut self) -> Option<Target> {
-        match self {
-            Self::IpAddr(inner) => inner.resolve_next().await,
-            Self::DomainWithPort(inner) => inner.resolve_next().await,
-            Self::DomainWithTransport(inner) => inner.resolve_next().await,
-            Self::JustDomain(inner) => inner.resolve_next().await,
+        #[cfg(feature = "tracing")]
+        let span = self.tracker().span();
+        let target = async {
+            match self {
+                Self::IpAddr(inner, _) => inner.resolve_next().await,
+                Self::DomainWithPort(inner, _) => inner.resolve_next().await,
+                Self::DomainWithTransport(inner, _) => inner.resolve_next().await,
+                Self::JustDomain(inner, _) => inner.resolve_next().await,
+            }
+        };
+        #[cfg(feature = "tracing")]
+        let target =
+            tracing::Instrument::instrument(target, span.unwrap_or_else(tracing::Span::none));
+        let started = Instant::now();
+        let target = target.await;
+
+        match &target {
+            Some(target) => {
+                self.tracker().push(TraceEvent::Target(target.clone()));
+                self.tracker().target_yielded(target, started.elapsed());
+            }
+            None => self.tracker().exhausted(),
         }
+
+        target
     }
 }
 
 impl<C> From<Context<C>> for Lookup<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     fn from(ctx: Context<C>) -> Self {
-        match ctx.host {
+        #[cfg(feature = "tracing")]
+        let span = crate::instrumentation::lookup_span(&ctx);
+        let lookup = match ctx.host {
             Host::IpAddr(ip_addr) => ip_addr_lookup(ip_addr, ctx),
             Host::Domain(ref domain) => match (ctx.port, ctx.transport) {
                 (Some(port), _) => domain_with_port_lookup(domain.clone(), port, ctx),
@@ -55,25 +171,38 @@ where
                 }
                 (None, None) => just_domain_lookup(domain.clone(), ctx),
             },
-        }
+        };
+        #[cfg(feature = "tracing")]
+        lookup.tracker().set_span(span);
+
+        lookup
     }
 }
 
 fn ip_addr_lookup<C: DnsClient>(ip_addr: IpAddr, ctx: Context<C>) -> Lookup<C> {
-    Lookup::IpAddr(ResolvableIpAddr::new(
-        ip_addr,
-        ctx.default_transport().default_port(),
-        ctx.default_transport(),
-    ))
+    Lookup::IpAddr(
+        ResolvableIpAddr::new(
+            ip_addr,
+            ctx.default_transport().default_port(),
+            ctx.default_transport(),
+        )
+        .with_provenance(vec![ProvenanceStep::IpAddr(ip_addr)].into()),
+        Tracker::with_limits(&ctx.limits)
+            .with_prefetch(ctx.prefetch)
+            .with_srv_selection(ctx.srv_selection.clone()),
+    )
 }
 
 fn domain_with_port_lookup<C: DnsClient>(domain: Domain, port: Port, ctx: Context<C>) -> Lookup<C> {
-    Lookup::DomainWithPort(ResolvableAddrRecord::new(
-        ctx.dns_client.clone(),
-        domain,
-        port,
-        ctx.default_transport(),
-    ))
+    let tracker = Tracker::with_limits(&ctx.limits)
+        .with_prefetch(ctx.prefetch)
+        .with_srv_selection(ctx.srv_selection.clone());
+
+    Lookup::DomainWithPort(
+        ResolvableAddrRecord::new(ctx.dns_client.clone(), domain, port, ctx.default_transport())
+            .with_tracker(tracker.clone()),
+        tracker,
+    )
 }
 
 fn domain_with_transport_lookup<C: DnsClient>(
@@ -81,56 +210,63 @@ fn domain_with_transport_lookup<C: DnsClient>(
     transport: Transport,
     ctx: Context<C>,
 ) -> Lookup<C> {
-    let mut lookups: Vec<ResolvableEnum<C>> = vec![];
-
-    let srv_domain = SrvDomain { secure: ctx.secure, protocol: transport.protocol(), domain };
-    lookups.push(ResolvableSrvRecord::new(ctx.dns_client.clone(), srv_domain.clone()).into());
-    lookups.push(
-        ResolvableAddrRecord::new(
-            ctx.dns_client,
-            srv_domain.domain.clone(),
-            srv_domain.transport().default_port(),
-            srv_domain.transport(),
-        )
-        .into(),
-    );
+    let tracker = Tracker::with_limits(&ctx.limits)
+        .with_prefetch(ctx.prefetch)
+        .with_srv_selection(ctx.srv_selection.clone());
+    let lookups = plan::domain_with_transport_plan(domain, transport, &ctx)
+        .into_iter()
+        .map(|step| resolvable_from(step, &ctx.dns_client, &tracker))
+        .collect::<Vec<ResolvableEnum<C>>>();
 
-    Lookup::DomainWithTransport(ResolvableVec::non_empty(lookups))
+    Lookup::DomainWithTransport(
+        ResolvableVec::non_empty(lookups).with_prefetch(tracker.prefetch()),
+        tracker,
+    )
 }
 
 fn just_domain_lookup<C: DnsClient>(domain: Domain, ctx: Context<C>) -> Lookup<C> {
-    let mut lookups: Vec<ResolvableEnum<C>> = vec![ResolvableNaptrRecord::new(
-        ctx.dns_client.clone(),
-        domain.clone(),
-        ctx.available_transports(),
-    )
-    .into()];
-
-    ctx.available_protocols().into_iter().for_each(|transport| {
-        let srv_domain = SrvDomain {
-            secure: ctx.secure,
-            protocol: transport.protocol(),
-            domain: domain.clone(),
-        };
+    let tracker = Tracker::with_limits(&ctx.limits)
+        .with_prefetch(ctx.prefetch)
+        .with_srv_selection(ctx.srv_selection.clone());
+    let lookups = plan::just_domain_plan(domain, &ctx)
+        .into_iter()
+        .map(|step| resolvable_from(step, &ctx.dns_client, &tracker))
+        .collect::<Vec<ResolvableEnum<C>>>();
 
-        lookups.push(ResolvableSrvRecord::new(ctx.dns_client.clone(), srv_domain).into());
-    });
-
-    let default_transport = match ctx.secure {
-        true => Transport::default_secure_transport(),
-        false => Transport::default_insecure_transport(),
-    };
-    lookups.push(
-        ResolvableAddrRecord::new(
-            ctx.dns_client,
-            domain,
-            default_transport.default_port(),
-            default_transport,
-        )
-        .into(),
-    );
+    Lookup::JustDomain(ResolvableVec::non_empty(lookups).with_prefetch(tracker.prefetch()), tracker)
+}
 
-    Lookup::JustDomain(ResolvableVec::non_empty(lookups))
+fn resolvable_from<C: DnsClient>(
+    step: PlannedStep,
+    dns_client: &C,
+    tracker: &Tracker,
+) -> ResolvableEnum<C> {
+    match step {
+        PlannedStep::IpAddr { ip_addr, port, transport } => {
+            ResolvableIpAddr::new(ip_addr, port, transport)
+                .with_provenance(vec![ProvenanceStep::IpAddr(ip_addr)].into())
+                .into()
+        }
+        PlannedStep::Naptr { domain, transports } => {
+            ResolvableNaptrRecord::new(dns_client.clone(), domain, transports)
+                .with_tracker(tracker.clone())
+                .into()
+        }
+        PlannedStep::Srv(srv_domain) => ResolvableSrvRecord::new(dns_client.clone(), srv_domain)
+            .with_tracker(tracker.clone())
+            .into(),
+        PlannedStep::Addr { domain, port, transport } => {
+            ResolvableAddrRecord::new(dns_client.clone(), domain, port, transport)
+                .with_tracker(tracker.clone())
+                .into()
+        }
+        PlannedStep::AddrFallback { domain, port, transport } => {
+            ResolvableAddrRecord::new(dns_client.clone(), domain, port, transport)
+                .with_tracker(tracker.clone())
+                .as_srv_fallback()
+                .into()
+        }
+    }
 }
 
 /*
@@ -0,0 +1,157 @@
+use crate::{
+    records::{SrvDomain, SrvService},
+    Context, DnsClient,
+};
+use rsip::{Domain, Host, Port, Transport};
+use std::net::IpAddr;
+
+/// A dry run of a [Lookup](super::Lookup): the ordered list of steps the lookup would go through
+/// for a [Context], without sending any DNS query. Use [Context::plan] to get one.
+///
+/// Note that the plan only has the steps that are known upfront. For instance, the SRV queries
+/// that follow the NAPTR answer depend on the answer itself, hence are not part of it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Plan(Vec<PlannedStep>);
+
+/// A single step of a [Plan].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PlannedStep {
+    /// Use the ip address as is, with the given port and transport (no DNS query).
+    IpAddr { ip_addr: IpAddr, port: Port, transport: Transport },
+    /// Query the NAPTR records of the domain, following only entries of the given transports.
+    Naptr { domain: Domain, transports: Vec<Transport> },
+    /// Query the SRV records of the SRV domain.
+    Srv(SrvDomain),
+    /// Query the A/AAAA records of the domain and use them with the given port and transport.
+    Addr { domain: Domain, port: Port, transport: Transport },
+    /// Same as [PlannedStep::Addr] but with default port and transport, used as the RFC 3263
+    /// fallback when the SRV queries find nothing.
+    AddrFallback { domain: Domain, port: Port, transport: Transport },
+}
+
+impl<C: DnsClient> Context<C> {
+    /// Returns the [Plan] of the [Lookup](super::Lookup) that would be created out of this
+    /// context, without touching the DNS.
+    pub fn plan(&self) -> Plan {
+        match &self.host {
+            Host::IpAddr(ip_addr) => ip_addr_plan(*ip_addr, self),
+            Host::Domain(domain) => match (self.port, self.transport) {
+                (Some(port), _) => domain_with_port_plan(domain.clone(), port, self),
+                (None, Some(transport)) => {
+                    domain_with_transport_plan(domain.clone(), transport, self)
+                }
+                (None, None) => just_domain_plan(domain.clone(), self),
+            },
+        }
+    }
+}
+
+impl Plan {
+    pub fn steps(&self) -> &[PlannedStep] {
+        self.0.as_slice()
+    }
+}
+
+impl IntoIterator for Plan {
+    type Item = PlannedStep;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl std::fmt::Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, step) in self.0.iter().enumerate() {
+            writeln!(f, "{}. {}", index + 1, step)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for PlannedStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IpAddr { ip_addr, port, transport } => {
+                write!(f, "use {} port {} {}", ip_addr, port, transport)
+            }
+            Self::Naptr { domain, transports } => {
+                let transports = transports.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "NAPTR {} for {}", domain, transports.join(", "))
+            }
+            Self::Srv(srv_domain) => write!(f, "SRV {}", srv_domain),
+            Self::Addr { domain, port, transport } => {
+                write!(f, "A/AAAA {} port {} {}", domain, port, transport)
+            }
+            Self::AddrFallback { domain, port, transport } => {
+                write!(f, "A/AAAA {} port {} {} (fallback)", domain, port, transport)
+            }
+        }
+    }
+}
+
+pub(super) fn ip_addr_plan<C: DnsClient>(ip_addr: IpAddr, ctx: &Context<C>) -> Plan {
+    Plan(vec![PlannedStep::IpAddr {
+        ip_addr,
+        port: ctx.default_transport().default_port(),
+        transport: ctx.default_transport(),
+    }])
+}
+
+pub(super) fn domain_with_port_plan<C: DnsClient>(
+    domain: Domain,
+    port: Port,
+    ctx: &Context<C>,
+) -> Plan {
+    Plan(vec![PlannedStep::Addr { domain, port, transport: ctx.default_transport() }])
+}
+
+pub(super) fn domain_with_transport_plan<C: DnsClient>(
+    domain: Domain,
+    transport: Transport,
+    ctx: &Context<C>,
+) -> Plan {
+    let srv_domain = SrvDomain {
+        secure: ctx.secure,
+        service: SrvService::Sip,
+        protocol: transport.protocol(),
+        domain,
+    };
+
+    Plan(vec![
+        PlannedStep::Srv(srv_domain.clone()),
+        PlannedStep::AddrFallback {
+            port: srv_domain.transport().default_port(),
+            transport: srv_domain.transport(),
+            domain: srv_domain.domain,
+        },
+    ])
+}
+
+pub(super) fn just_domain_plan<C: DnsClient>(domain: Domain, ctx: &Context<C>) -> Plan {
+    let mut steps =
+        vec![PlannedStep::Naptr { domain: domain.clone(), transports: ctx.available_transports() }];
+
+    ctx.available_protocols().into_iter().for_each(|transport| {
+        steps.push(PlannedStep::Srv(SrvDomain {
+            secure: ctx.secure,
+            service: SrvService::Sip,
+            protocol: transport.protocol(),
+            domain: domain.clone(),
+        }));
+    });
+
+    let default_transport = match ctx.secure {
+        true => Transport::default_secure_transport(),
+        false => Transport::default_insecure_transport(),
+    };
+    steps.push(PlannedStep::AddrFallback {
+        domain,
+        port: default_transport.default_port(),
+        transport: default_transport,
+    });
+
+    Plan(steps)
+}
@@ -0,0 +1,117 @@
+use crate::{Observer, QueryOutcome, QueryType, Target};
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
+use std::time::Duration;
+
+/// An [Observer] that records, through the [metrics](https://docs.rs/metrics) facade:
+///
+/// * `rsip_dns_queries_total`: the queries, by `record_type` (`naptr`, `srv` or `addr`) and
+///   `outcome` (see [QueryOutcome::as_str])
+/// * `rsip_dns_queries_in_flight`: the queries in flight, by `record_type`
+/// * `rsip_dns_query_duration_seconds`: the latency of the queries, by `record_type`
+/// * `rsip_dns_cache_hits_total`: the records that came along with another answer, hence
+///   needed no query, by `record_type`. The cache hit ratio of a record type is
+///   `cache_hits / (cache_hits + queries)`
+/// * `rsip_dns_empty_branches_total`: the branches that led to nothing, by `record_type`
+/// * `rsip_dns_time_to_first_target_seconds`: the time it took to resolve the first target of
+///   each lookup, from the first call to `resolve_next` (not from the creation of the lookup)
+/// * `rsip_dns_targets_tried`: how many targets were tried before one succeeded (see
+///   [Lookup::report_success](crate::Lookup::report_success))
+/// * `rsip_dns_lookups_exhausted_total`: the lookups that ran out of targets
+///
+/// It's available under the `metrics` feature flag. Any exporter (like a Prometheus one) needs
+/// to be installed separately.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsObserver;
+
+impl MetricsObserver {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Registers the descriptions of the metrics with the installed recorder.
+    pub fn describe() {
+        describe_counter!("rsip_dns_queries_total", "DNS queries, by record type and outcome");
+        describe_gauge!("rsip_dns_queries_in_flight", "DNS queries in flight, by record type");
+        describe_histogram!(
+            "rsip_dns_query_duration_seconds",
+            metrics::Unit::Seconds,
+            "DNS query latency, by record type"
+        );
+        describe_counter!(
+            "rsip_dns_cache_hits_total",
+            "Records that came along with another answer, by record type"
+        );
+        describe_counter!(
+            "rsip_dns_empty_branches_total",
+            "Lookup branches that led to nothing, by record type"
+        );
+        describe_histogram!(
+            "rsip_dns_time_to_first_target_seconds",
+            metrics::Unit::Seconds,
+            "Time from the first resolve_next of a lookup to its first target"
+        );
+        describe_histogram!(
+            "rsip_dns_targets_tried",
+            metrics::Unit::Count,
+            "Targets tried before one succeeded"
+        );
+        describe_counter!("rsip_dns_lookups_exhausted_total", "Lookups that ran out of targets");
+    }
+}
+
+impl Observer for MetricsObserver {
+    fn query_started(&self, query_type: QueryType, _domain: &str) {
+        gauge!("rsip_dns_queries_in_flight", "record_type" => label_of(query_type)).increment(1);
+    }
+
+    fn query_ended(
+        &self,
+        query_type: QueryType,
+        _domain: &str,
+        outcome: QueryOutcome,
+        latency: Duration,
+    ) {
+        let record_type = label_of(query_type);
+
+        gauge!("rsip_dns_queries_in_flight", "record_type" => record_type).decrement(1);
+        counter!(
+            "rsip_dns_queries_total",
+            "record_type" => record_type,
+            "outcome" => outcome.as_str()
+        )
+        .increment(1);
+        histogram!("rsip_dns_query_duration_seconds", "record_type" => record_type).record(latency);
+    }
+
+    fn query_preresolved(&self, query_type: QueryType, _domain: &str) {
+        counter!("rsip_dns_cache_hits_total", "record_type" => label_of(query_type)).increment(1);
+    }
+
+    fn branch_empty(&self, query_type: QueryType, _domain: &str) {
+        counter!("rsip_dns_empty_branches_total", "record_type" => label_of(query_type))
+            .increment(1);
+    }
+
+    fn target_yielded(&self, _target: &Target, attempt: usize, latency: Duration) {
+        if attempt == 1 {
+            histogram!("rsip_dns_time_to_first_target_seconds").record(latency);
+        }
+    }
+
+    fn target_succeeded(&self, attempt: usize) {
+        histogram!("rsip_dns_targets_tried").record(attempt as f64);
+    }
+
+    fn lookup_exhausted(&self, _targets: usize) {
+        counter!("rsip_dns_lookups_exhausted_total").increment(1);
+    }
+}
+
+fn label_of(query_type: QueryType) -> &'static str {
+    match query_type {
+        QueryType::Naptr => "naptr",
+        QueryType::Srv => "srv",
+        QueryType::Addr => "addr",
+        QueryType::Cname => "cname",
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    records::{AddrRecord, NaptrRecord, SrvRecord},
+    QueryType, Target,
+};
+use rsip::Error;
+use std::time::Duration;
+
+/// Hooks into a [Lookup](super::Lookup), meant for metrics: the lookup and its resolvables call
+/// the observer at key points, like when a query starts and ends or when a target is yielded.
+/// Set one with [Lookup::with_observer](super::Lookup::with_observer). Every method does nothing
+/// by default.
+///
+/// Under the `metrics` feature flag, [MetricsObserver](super::MetricsObserver) records them
+/// through the [metrics](https://docs.rs/metrics) facade.
+pub trait Observer: std::fmt::Debug + Send + Sync {
+    /// A query was sent to the [DnsClient](super::DnsClient).
+    fn query_started(&self, _query_type: QueryType, _domain: &str) {}
+
+    /// A query ended, after the given time.
+    fn query_ended(
+        &self,
+        _query_type: QueryType,
+        _domain: &str,
+        _outcome: QueryOutcome,
+        _latency: Duration,
+    ) {
+    }
+
+    /// No query was needed, since the record came along with the answer of another query (like
+    /// SRV records in the additional section of a NAPTR answer). It's the closest this crate gets
+    /// to a cache hit, any caching of the [DnsClient](super::DnsClient) is invisible to it.
+    fn query_preresolved(&self, _query_type: QueryType, _domain: &str) {}
+
+    /// A NAPTR, SRV or A/AAAA branch led to nothing to follow: the query found nothing, failed,
+    /// was not allowed by the [Limits](super::Limits) or none of the entries could be used.
+    fn branch_empty(&self, _query_type: QueryType, _domain: &str) {}
+
+    /// The lookup yielded a target, `attempt` being its position (1 for the first target) and
+    /// `latency` the time it took to resolve it.
+    fn target_yielded(&self, _target: &Target, _attempt: usize, _latency: Duration) {}
+
+    /// The target of the given attempt worked, as reported by
+    /// [Lookup::report_success](super::Lookup::report_success).
+    fn target_succeeded(&self, _attempt: usize) {}
+
+    /// The lookup has no more targets, after yielding the given number of them.
+    fn lookup_exhausted(&self, _targets: usize) {}
+}
+
+/// How a query ended, as given to [Observer::query_ended].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum QueryOutcome {
+    /// The answer had the given number of records (NAPTR or SRV entries, or addresses).
+    Answered(usize),
+    /// The answer had no records.
+    Empty,
+    /// The query failed. Only A/AAAA queries tell failures apart from empty answers, the
+    /// [Lookup](super::Lookup) uses the NAPTR and SRV queries that can't fail.
+    Failed,
+    /// The deadline of the lookup was reached while the query was in flight.
+    DeadlineExceeded,
+    /// The query was dropped while in flight, like when the [Lookup](super::Lookup) itself is
+    /// dropped or its `resolve_next` future is cancelled by a timeout.
+    Cancelled,
+}
+
+impl QueryOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Answered(_) => "answered",
+            Self::Empty => "empty",
+            Self::Failed => "failed",
+            Self::DeadlineExceeded => "deadline_exceeded",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+//what the observer (and the tracing spans) learn about the answer of a query
+pub(crate) trait Answer {
+    fn outcome(&self) -> QueryOutcome;
+
+    #[cfg(feature = "tracing")]
+    fn error(&self) -> Option<&Error> {
+        None
+    }
+}
+
+fn outcome_of(records: usize) -> QueryOutcome {
+    match records {
+        0 => QueryOutcome::Empty,
+        records => QueryOutcome::Answered(records),
+    }
+}
+
+impl Answer for Option<NaptrRecord> {
+    fn outcome(&self) -> QueryOutcome {
+        outcome_of(self.as_ref().map(|record| record.entries.len()).unwrap_or(0))
+    }
+}
+
+impl Answer for Option<SrvRecord> {
+    fn outcome(&self) -> QueryOutcome {
+        outcome_of(self.as_ref().map(|record| record.entries.len()).unwrap_or(0))
+    }
+}
+
+impl Answer for Result<AddrRecord, Error> {
+    fn outcome(&self) -> QueryOutcome {
+        match self {
+            Ok(record) => outcome_of(record.ip_addrs.len()),
+            Err(_) => QueryOutcome::Failed,
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn error(&self) -> Option<&Error> {
+        self.as_ref().err()
+    }
+}
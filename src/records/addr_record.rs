@@ -2,8 +2,10 @@ use rsip::Domain;
 use std::net::IpAddr;
 
 /// Simple struct that holds the A record details (domain and ip entries)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AddrRecord {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::domain"))]
     pub domain: Domain,
     pub ip_addrs: Vec<IpAddr>,
 }
@@ -11,15 +11,59 @@ pub use srv_record::{SrvEntry, SrvRecord};
 use rsip::{Domain, Error, Transport};
 use std::convert::TryFrom;
 
-/// Simple struct that holds the srv domain properties (namely actual domain, protocol and whether
-/// it's secure or not).
+/// Simple struct that holds the srv domain properties (namely actual domain, service, protocol and
+/// whether it's secure or not).
+///
+/// The service defaults to SIP, but any service label can be used, so the same SRV machinery can
+/// resolve `_stun._udp`, `_turns._tcp` etc domains.
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct SrvDomain {
     pub domain: Domain,
+    pub service: SrvService,
     pub protocol: Transport,
     pub secure: bool,
 }
 
+/// The service part of a [SrvDomain] (the `_service` label of `_service._proto.name`).
+///
+/// For the well known services the `secure` flag of the [SrvDomain] picks the secure variant of
+/// the label (`_sips`, `_stuns`, `_turns`). [SrvService::Other] labels are used verbatim.
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Default)]
+pub enum SrvService {
+    #[default]
+    Sip,
+    Stun,
+    Turn,
+    Other(String),
+}
+
+impl SrvService {
+    pub fn label(&self, secure: bool) -> String {
+        match (self, secure) {
+            (Self::Sip, false) => "sip".into(),
+            (Self::Sip, true) => "sips".into(),
+            (Self::Stun, false) => "stun".into(),
+            (Self::Stun, true) => "stuns".into(),
+            (Self::Turn, false) => "turn".into(),
+            (Self::Turn, true) => "turns".into(),
+            (Self::Other(label), _) => label.clone(),
+        }
+    }
+
+    // returns the service along with whether the label denotes the secure variant of it
+    fn from_label(label: &str) -> (Self, bool) {
+        match label {
+            part if part.eq_ignore_ascii_case("sip") => (Self::Sip, false),
+            part if part.eq_ignore_ascii_case("sips") => (Self::Sip, true),
+            part if part.eq_ignore_ascii_case("stun") => (Self::Stun, false),
+            part if part.eq_ignore_ascii_case("stuns") => (Self::Stun, true),
+            part if part.eq_ignore_ascii_case("turn") => (Self::Turn, false),
+            part if part.eq_ignore_ascii_case("turns") => (Self::Turn, true),
+            part => (Self::Other(part.into()), false),
+        }
+    }
+}
+
 impl SrvDomain {
     pub fn transport(&self) -> Transport {
         match (self.secure, self.protocol) {
@@ -52,23 +96,49 @@ impl TryFrom<NaptrEntry> for SrvDomain {
 
 impl From<(Domain, Transport)> for SrvDomain {
     fn from(tuple: (Domain, Transport)) -> Self {
-        Self { domain: tuple.0, protocol: tuple.1.protocol(), secure: false }
+        Self {
+            domain: tuple.0,
+            service: SrvService::Sip,
+            protocol: tuple.1.protocol(),
+            secure: false,
+        }
+    }
+}
+
+/// Builds a [SrvDomain] for any service. Whether it's secure or not is derived from the
+/// transport, so `(SrvService::Turn, domain, Transport::Tls)` becomes `_turns._tcp.domain`.
+/// [SrvService::Other] labels are kept verbatim and are never secure, like when parsed, so that
+/// the [SrvDomain] round-trips through its string form.
+impl From<(SrvService, Domain, Transport)> for SrvDomain {
+    fn from(tuple: (SrvService, Domain, Transport)) -> Self {
+        let secure = match tuple.0 {
+            SrvService::Other(_) => false,
+            _ => Transport::secure_transports().contains(&tuple.2),
+        };
+
+        Self { domain: tuple.1, service: tuple.0, protocol: tuple.2.protocol(), secure }
     }
 }
 
 impl std::fmt::Display for SrvDomain {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.secure {
-            true => {
-                write!(f, "_sips._{}.{}", self.protocol.to_string().to_lowercase(), self.domain)
-            }
-            false => {
-                write!(f, "_sip._{}.{}", self.protocol.to_string().to_lowercase(), self.domain)
-            }
-        }
+        write!(
+            f,
+            "_{}._{}.{}",
+            self.service.label(self.secure),
+            self.protocol.to_string().to_lowercase(),
+            self.domain
+        )
     }
 }
 
+//compares 2 domains ignoring case and the trailing dot of fully qualified names
+pub(crate) fn same_domain(first: &Domain, second: &Domain) -> bool {
+    let (first, second) = (first.to_string(), second.to_string());
+
+    first.trim_end_matches('.').eq_ignore_ascii_case(second.trim_end_matches('.'))
+}
+
 impl TryFrom<Domain> for SrvDomain {
     type Error = rsip::Error;
 
@@ -88,11 +158,13 @@ impl TryFrom<&str> for SrvDomain {
         };
         use std::convert::TryInto;
 
-        let (rem, (_, scheme, _)) =
+        let (rem, (_, service, _)) =
             tuple::<_, _, VerboseError<&str>, _>((tag("_"), take_until("."), tag(".")))(from)
-                .map_err(|_| Error::tokenizer(("SrvDomain scheme", from)))?;
-        let scheme: rsip::Scheme =
-            rsip::common::uri::scheme::Tokenizer::from(scheme.as_bytes()).try_into()?;
+                .map_err(|_| Error::tokenizer(("SrvDomain service", from)))?;
+        if service.is_empty() {
+            return Err(Error::tokenizer(("SrvDomain service", from)));
+        }
+        let (service, secure) = SrvService::from_label(service);
 
         let (domain, (_, transport, _)) =
             tuple::<_, _, VerboseError<&str>, _>((tag("_"), take_until("."), tag(".")))(rem)
@@ -100,10 +172,6 @@ impl TryFrom<&str> for SrvDomain {
         let transport: rsip::Transport =
             rsip::common::transport::Tokenizer::from(transport.as_bytes()).try_into()?;
 
-        Ok(Self {
-            secure: scheme.is_sips()?,
-            protocol: transport.protocol(),
-            domain: domain.into(),
-        })
+        Ok(Self { secure, service, protocol: transport.protocol(), domain: domain.into() })
     }
 }
@@ -1,28 +1,40 @@
+use super::{SrvDomain, SrvRecord};
 use rsip::{Domain, Error, Transport};
 use std::collections::VecDeque;
 use std::convert::TryFrom;
 
 /// Simple struct that holds the NAPTR record details (domain and srv entries)
-#[derive(Debug, Clone)]
+///
+/// `additional_srvs` holds any SRV records of the replacements that came along with the answer
+/// (usually in the additional section), along with their own pre-resolved addresses. These are
+/// used as is, instead of querying the replacements again. Note that the `trust-dns` clients can't
+/// fill them, see the `trust_dns` module.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NaptrRecord {
     pub entries: Vec<NaptrEntry>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::domain"))]
     pub domain: Domain,
+    pub additional_srvs: Vec<SrvRecord>,
 }
 
 /// Simple struct that resembles the NAPTR record entries
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NaptrEntry {
     pub order: u16,
     pub preference: u16,
     pub flags: NaptrFlags,
     pub services: NaptrServices,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::regexp"))]
     pub regexp: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::domain"))]
     pub replacement: Domain,
 }
 
 //TODO: this should be a vec of NaptrFlag, with some handy methods to check if there is only 1
 //specific flag or a specific flag is contained (in our case S flag is what we care)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum NaptrFlags {
     S,
     A,
@@ -33,7 +45,7 @@ pub enum NaptrFlags {
 
 //TODO: this should be a vec of NaptrServices, with some handy methods to check if there is only 1
 //specific service or a specific service is contained (in our case SIP(S)+D2x services is what we care)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum NaptrServices {
     SipD2t,
     SipD2u,
@@ -51,13 +63,25 @@ impl From<&[u8]> for NaptrFlags {
         match from {
             s if s == b"S" => Self::S,
             s if s == b"A" => Self::A,
-            s if s == b"A" => Self::U,
+            s if s == b"U" => Self::U,
             s if s == b"P" => Self::P,
             s => Self::Other(s.to_vec()),
         }
     }
 }
 
+impl NaptrFlags {
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::S => b"S",
+            Self::A => b"A",
+            Self::U => b"U",
+            Self::P => b"P",
+            Self::Other(other) => other.as_slice(),
+        }
+    }
+}
+
 impl NaptrEntry {
     pub fn total_weight(&self) -> u16 {
         self.order + self.preference
@@ -85,6 +109,20 @@ impl NaptrRecord {
         self.entries.iter()
     }
 
+    /// Returns the pre-resolved SRV record of the given replacement, if one came along.
+    pub fn srv_record_for(&self, srv_domain: &SrvDomain) -> Option<SrvRecord> {
+        self.additional_srvs
+            .iter()
+            .find(|srv_record| {
+                let other = &srv_record.domain;
+
+                (&other.service, other.protocol, other.secure)
+                    == (&srv_domain.service, srv_domain.protocol, srv_domain.secure)
+                    && super::same_domain(&other.domain, &srv_domain.domain)
+            })
+            .cloned()
+    }
+
     pub fn sorted(mut self) -> Self {
         use std::cmp::Reverse;
 
@@ -132,6 +170,22 @@ impl NaptrServices {
     }
 }
 
+impl std::fmt::Display for NaptrServices {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SipD2t => write!(f, "SIP+D2T"),
+            Self::SipD2u => write!(f, "SIP+D2U"),
+            Self::SipD2s => write!(f, "SIP+D2S"),
+            Self::SipD2w => write!(f, "SIP+D2W"),
+            Self::SipsD2t => write!(f, "SIPS+D2T"),
+            Self::SipsD2u => write!(f, "SIPS+D2U"),
+            Self::SipsD2s => write!(f, "SIPS+D2S"),
+            Self::SipsD2w => write!(f, "SIPS+D2W"),
+            Self::Other(other) => write!(f, "{}", other),
+        }
+    }
+}
+
 impl TryFrom<&[u8]> for NaptrServices {
     type Error = Error;
 
@@ -152,6 +206,25 @@ impl TryFrom<&[u8]> for NaptrServices {
     }
 }
 
+/// The inverse of [NaptrServices::transport]: `SIPS+D2T` for TLS, `SIP+D2U` for UDP etc.
+impl TryFrom<Transport> for NaptrServices {
+    type Error = Error;
+
+    fn try_from(from: Transport) -> Result<Self, Self::Error> {
+        match from {
+            Transport::Tcp => Ok(Self::SipD2t),
+            Transport::Udp => Ok(Self::SipD2u),
+            Transport::Sctp => Ok(Self::SipD2s),
+            Transport::Ws => Ok(Self::SipD2w),
+            Transport::Tls => Ok(Self::SipsD2t),
+            Transport::Wss => Ok(Self::SipsD2w),
+            transport => {
+                Err(Error::Unexpected(format!("no NAPTR service for transport {}", transport)))
+            }
+        }
+    }
+}
+
 #[cfg(feature = "test-utils")]
 impl testing_utils::Randomize for NaptrEntry {
     fn random() -> Self {
@@ -1,19 +1,28 @@
-use super::SrvDomain;
+use super::{AddrRecord, SrvDomain};
 use rsip::{Domain, Port, Transport};
 
 /// Simple struct that holds the SRV record details (domain and srv entries)
-#[derive(Debug, Clone)]
+///
+/// `additional_addrs` holds any address records of the targets that came along with the answer
+/// (usually in the additional section). These are used as is, instead of querying the targets
+/// again.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SrvRecord {
     pub entries: Vec<SrvEntry>,
     pub domain: SrvDomain,
+    pub additional_addrs: Vec<AddrRecord>,
 }
 
 /// Simple struct that resembles the SRV record entries
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SrvEntry {
     pub priority: u16,
     pub weight: u16,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::port"))]
     pub port: Port,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::domain"))]
     pub target: Domain,
 }
 
@@ -30,18 +39,93 @@ impl SrvRecord {
         self.domain.transport()
     }
 
+    /// Returns the pre-resolved address record of the given target, if one came along.
+    pub fn addr_record_for(&self, target: &Domain) -> Option<AddrRecord> {
+        self.additional_addrs
+            .iter()
+            .find(|addr_record| super::same_domain(&addr_record.domain, target))
+            .cloned()
+    }
+
     pub fn sorted(mut self) -> Self {
         use std::cmp::Reverse;
 
         self.entries.sort_by_key(|b| Reverse(b.total_weight()));
         self
     }
+
+    /// Orders the entries by priority (lowest first) and, among the entries of the same
+    /// priority, by weighted rendezvous hashing on the given key: the same key always gets the
+    /// same order, while different keys spread across the entries in proportion to their weight.
+    /// Adding or removing an entry only moves the keys that pick (or picked) that entry.
+    ///
+    /// The hash is stable across processes, so all nodes of a cluster agree on the order.
+    pub fn rendezvous_sorted(mut self, key: &str) -> Self {
+        let has_weights = |priority: u16| {
+            self.entries.iter().any(|entry| entry.priority == priority && entry.weight > 0)
+        };
+        let mut scored = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let weight = match (entry.weight, has_weights(entry.priority)) {
+                    (0, true) => ZERO_WEIGHT,
+                    (0, false) => 1.0,
+                    (weight, _) => weight as f64,
+                };
+                let score = -weight / unit_hash(key, entry).ln();
+                (entry.clone(), score)
+            })
+            .collect::<Vec<_>>();
+
+        scored.sort_by(|(a, a_score), (b, b_score)| {
+            a.priority.cmp(&b.priority).then(b_score.total_cmp(a_score))
+        });
+        self.entries = scored.into_iter().map(|(entry, _)| entry).collect();
+        self
+    }
+
+    /// Returns true when the record has a single entry with a `.` target, which according to
+    /// [RFC 2782](https://datatracker.ietf.org/doc/html/rfc2782) means that the service is
+    /// decidedly not available at this domain.
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self.entries.as_slice(), [entry] if entry.has_root_target())
+    }
 }
 
 impl SrvEntry {
     pub fn total_weight(&self) -> u16 {
         (10000 - self.priority) + self.weight
     }
+
+    /// Returns true when the target is the root domain (`.`), which is never a valid host.
+    pub fn has_root_target(&self) -> bool {
+        matches!(self.target.to_string().as_str(), "." | "")
+    }
+}
+
+//RFC 2782: entries of weight 0 should have a very small chance of being selected, when there
+//are entries with weight in the same priority
+const ZERO_WEIGHT: f64 = 0.01;
+
+//hashes the key along with the entry to (0, 1), using FNV-1a followed by the splitmix64 finalizer,
+//which (unlike the std hashers) is guaranteed to be the same everywhere
+fn unit_hash(key: &str, entry: &SrvEntry) -> f64 {
+    let target = entry.target.to_string().to_lowercase();
+    let bytes = key
+        .bytes()
+        .chain(std::iter::once(0))
+        .chain(target.trim_end_matches('.').bytes())
+        .chain(std::iter::once(0))
+        .chain(entry.port.to_string().into_bytes());
+
+    let mut hash = bytes
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^= hash >> 31;
+
+    ((hash >> 11) as f64 + 0.5) / (1u64 << 53) as f64
 }
 
 impl IntoIterator for SrvRecord {
@@ -60,12 +144,28 @@ impl testing_utils::Randomize for SrvDomain {
 
         SrvDomain {
             domain: Randomize::random(),
+            service: Default::default(),
             protocol: Randomize::random(),
             secure: bool::random(),
         }
     }
 }
 
+#[cfg(feature = "test-utils")]
+impl SrvDomain {
+    /// Like [Randomize::random](testing_utils::Randomize::random), which only generates SIP
+    /// domains, but for any of the known services (SIP, STUN or TURN).
+    pub fn random_with_any_service() -> Self {
+        use super::SrvService;
+        use testing_utils::Randomize;
+
+        SrvDomain {
+            service: testing_utils::sample(&[SrvService::Sip, SrvService::Stun, SrvService::Turn]),
+            ..Randomize::random()
+        }
+    }
+}
+
 #[cfg(feature = "test-utils")]
 impl testing_utils::Randomize for SrvEntry {
     fn random() -> Self {
@@ -86,15 +186,17 @@ impl testing_utils::Randomize for SrvEntry {
     }
 }
 
-/*
 #[cfg(feature = "test-utils")]
-impl crate::Randomize for SrvRecord {
+impl testing_utils::Randomize for SrvRecord {
     fn random() -> Self {
-        use crate::Randomize;
+        use testing_utils::Randomize;
 
-        (2..5)
-            .map(|_| SrvEntry::random())
-            .collect::<Vec<_>>()
-            .into()
+        Self {
+            domain: Randomize::random(),
+            entries: (0..testing_utils::rand_num_from(2..5))
+                .map(|_| SrvEntry::random())
+                .collect::<Vec<_>>(),
+            additional_addrs: vec![],
+        }
     }
-}*/
+}
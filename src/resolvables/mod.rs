@@ -18,12 +18,17 @@ pub use resolvable_addr_record::ResolvableAddrRecord;
 pub use resolvable_enum::ResolvableEnum;
 pub use resolvable_ip_addr::ResolvableIpAddr;
 pub use resolvable_naptr_record::ResolvableNaptrRecord;
-pub use resolvable_srv_record::ResolvableSrvRecord;
+pub use resolvable_srv_record::{ResolvableSrvRecord, SrvSelection};
 pub use resolvable_vec::ResolvableVec;
 
 use async_trait::async_trait;
+use futures::future::{BoxFuture, Shared};
 use std::collections::VecDeque;
 
+//a query kept in flight by ResolvableExt::prefetch, giving None if the limits of the lookup didn't
+//allow it
+type Prefetched<T> = Shared<BoxFuture<'static, Option<T>>>;
+
 /// ResolvableState communicates whether a type that implements `ResolvableExt` entry has not been
 /// touched/opened yet ([ResolvableState::Unset]), it has been "opened" and has still
 /// remaining stuff in it ([ResolvableState::NonEmpty]) or it has been "opened" and possibly
@@ -66,6 +71,14 @@ where
     /// (it's just an `async fn resolve_next(&mut self) -> Option<I>`).
     async fn resolve_next(&mut self) -> Option<I>;
 
+    /// Sends, ahead of time, the DNS query this resolvable needs before it can return its first
+    /// item, keeping the answer for [ResolvableExt::resolve_next]. The query keeps its progress
+    /// even if the returned future is dropped before completing, so it can be polled for a while
+    /// and resumed later. Used by a [ResolvableVec] to prefetch its upcoming items.
+    ///
+    /// The default implementation does nothing.
+    async fn prefetch(&mut self) {}
+
     fn is_empty(&self) -> bool {
         matches!(self.state(), ResolvableState::Empty)
     }
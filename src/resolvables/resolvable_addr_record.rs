@@ -1,26 +1,34 @@
 use crate::{
-    resolvables::{ResolvableExt, ResolvableIpAddr, ResolvableState, ResolvableVec},
-    DnsClient, Target,
+    records::AddrRecord,
+    resolvables::{Prefetched, ResolvableExt, ResolvableIpAddr, ResolvableState, ResolvableVec},
+    tracker::{TraceEvent, Tracker},
+    DnsClient, Provenance, ProvenanceStep, QueryType, Target,
 };
 use async_trait::async_trait;
-use rsip::{Domain, Port, Transport};
+use futures::future::{BoxFuture, FutureExt};
+use rsip::{Domain, Error, Port, Transport};
 
 #[derive(Debug, Clone)]
 pub struct ResolvableAddrRecord<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     dns_client: C,
     domain: Domain,
     port: Port,
     transport: Transport,
+    srv_fallback: bool,
+    preresolved: Option<AddrRecord>,
+    prefetched: Option<Prefetched<Result<AddrRecord, Error>>>,
+    tracker: Tracker,
+    provenance: Provenance,
     resolvable_ip_addrs: ResolvableVec<ResolvableIpAddr, Target>,
 }
 
 #[async_trait]
 impl<C> ResolvableExt<Target> for ResolvableAddrRecord<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     fn state(&self) -> ResolvableState {
         self.resolvable_ip_addrs.state()
@@ -33,23 +41,147 @@ where
 
         self.resolvable_ip_addrs.resolve_next().await
     }
+
+    //A/AAAA fallbacks are never prefetched, as they depend on the outcome of previous SRV queries
+    async fn prefetch(&mut self) {
+        if !self.resolvable_ip_addrs.is_unset() || self.preresolved.is_some() || self.srv_fallback {
+            return;
+        }
+
+        let prefetched = match &self.prefetched {
+            Some(prefetched) => prefetched.clone(),
+            None => {
+                let prefetched = self.query().shared();
+                self.prefetched = Some(prefetched.clone());
+                prefetched
+            }
+        };
+        prefetched.await;
+    }
 }
 
 impl<C> ResolvableAddrRecord<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     pub fn new(dns_client: C, domain: Domain, port: Port, transport: Transport) -> Self {
-        Self { dns_client, domain, port, transport, resolvable_ip_addrs: Default::default() }
+        Self {
+            dns_client,
+            domain,
+            port,
+            transport,
+            srv_fallback: false,
+            preresolved: None,
+            prefetched: None,
+            tracker: Default::default(),
+            provenance: Default::default(),
+            resolvable_ip_addrs: Default::default(),
+        }
+    }
+
+    /// Shares the given [Tracker] with this resolvable.
+    pub fn with_tracker(mut self, tracker: Tracker) -> Self {
+        self.tracker = tracker;
+        self
+    }
+
+    /// Sets the [Provenance] that led to this resolvable. The A/AAAA step is appended to it for
+    /// each [Target] yielded.
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
+    /// Marks this resolvable as the A/AAAA fallback that RFC 3263 performs when no SRV records
+    /// are found. A fallback is skipped altogether if a SRV lookup for the same domain and
+    /// transport (recorded in the shared [Tracker]) signaled that the service is not available.
+    pub fn as_srv_fallback(mut self) -> Self {
+        self.srv_fallback = true;
+        self
+    }
+
+    /// Uses the given (pre-resolved) address record instead of querying the dns client, for
+    /// instance when the addresses came along in the additional section of a SRV answer.
+    pub fn with_preresolved(mut self, addr_record: AddrRecord) -> Self {
+        self.preresolved = Some(addr_record);
+        self
+    }
+
+    //the query is owned ('static), so that it can be kept in flight when prefetched,
+    //None means that the limits of the lookup didn't allow it
+    fn query(&self) -> BoxFuture<'static, Option<Result<AddrRecord, Error>>> {
+        let dns_client = self.dns_client.clone();
+        let tracker = self.tracker.clone();
+        let domain = self.domain.clone();
+
+        async move {
+            let query = dns_client.ip_lookup(domain.clone());
+            let addr_record = tracker.query(TraceEvent::AddrQuery(domain.clone()), query).await?;
+            tracker.push(TraceEvent::AddrAnswer { domain, record: addr_record.clone() });
+            Some(addr_record)
+        }
+        .boxed()
     }
 
     async fn resolve_domain(&mut self) {
-        match self.dns_client.ip_lookup(self.domain.clone()).await {
+        self.resolve_ip_addrs().await;
+        if matches!(self.resolvable_ip_addrs.state(), ResolvableState::Empty) {
+            self.tracker.branch_empty(QueryType::Addr, &self.domain);
+        }
+    }
+
+    async fn resolve_ip_addrs(&mut self) {
+        if self.srv_fallback && self.tracker.srv_unavailable(&self.domain, self.transport) {
+            self.tracker.push(TraceEvent::AddrFallbackSkipped {
+                domain: self.domain.clone(),
+                transport: self.transport,
+            });
+            self.resolvable_ip_addrs = ResolvableVec::empty();
+            return;
+        }
+        #[cfg(feature = "tracing")]
+        if self.srv_fallback {
+            crate::instrumentation::addr_fallback_started(
+                self.tracker.span(),
+                &self.domain,
+                self.transport,
+            );
+        }
+
+        let addr_record = match self.preresolved.take() {
+            Some(addr_record) => {
+                self.tracker.push(TraceEvent::AddrPreresolved(addr_record.clone()));
+                Ok(addr_record)
+            }
+            None => {
+                let query = match self.prefetched.take() {
+                    Some(prefetched) => prefetched.await,
+                    None => self.query().await,
+                };
+
+                match query {
+                    Some(addr_record) => addr_record,
+                    None => {
+                        self.resolvable_ip_addrs = ResolvableVec::empty();
+                        return;
+                    }
+                }
+            }
+        };
+
+        match addr_record {
             Ok(a_record) => {
                 let resolvable_ip_addrs = a_record
                     .ip_addrs
                     .into_iter()
-                    .map(|ip_addr| ResolvableIpAddr::new(ip_addr, self.port, self.transport))
+                    .map(|ip_addr| {
+                        ResolvableIpAddr::new(ip_addr, self.port, self.transport).with_provenance(
+                            self.provenance.with(ProvenanceStep::Addr {
+                                domain: self.domain.clone(),
+                                ip_addr,
+                            }),
+                        )
+                    })
                     .collect::<Vec<_>>();
                 self.resolvable_ip_addrs = ResolvableVec::non_empty(resolvable_ip_addrs)
             }
@@ -10,7 +10,7 @@ use async_trait::async_trait;
 #[derive(Debug, Clone)]
 pub enum ResolvableEnum<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     IpAddr(ResolvableIpAddr),
     AddrRecord(ResolvableAddrRecord<C>),
@@ -21,7 +21,7 @@ where
 #[async_trait]
 impl<C> ResolvableExt<Target> for ResolvableEnum<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     fn state(&self) -> ResolvableState {
         match self {
@@ -40,27 +40,35 @@ where
             Self::NaptrRecord(inner) => inner.resolve_next().await,
         }
     }
+    async fn prefetch(&mut self) {
+        match self {
+            Self::IpAddr(inner) => inner.prefetch().await,
+            Self::AddrRecord(inner) => inner.prefetch().await,
+            Self::SrvRecord(inner) => inner.prefetch().await,
+            Self::NaptrRecord(inner) => inner.prefetch().await,
+        }
+    }
 }
 
-impl<C: DnsClient> From<ResolvableIpAddr> for ResolvableEnum<C> {
+impl<C: DnsClient + 'static> From<ResolvableIpAddr> for ResolvableEnum<C> {
     fn from(from: ResolvableIpAddr) -> Self {
         Self::IpAddr(from)
     }
 }
 
-impl<C: DnsClient> From<ResolvableAddrRecord<C>> for ResolvableEnum<C> {
+impl<C: DnsClient + 'static> From<ResolvableAddrRecord<C>> for ResolvableEnum<C> {
     fn from(from: ResolvableAddrRecord<C>) -> Self {
         Self::AddrRecord(from)
     }
 }
 
-impl<C: DnsClient> From<ResolvableSrvRecord<C>> for ResolvableEnum<C> {
+impl<C: DnsClient + 'static> From<ResolvableSrvRecord<C>> for ResolvableEnum<C> {
     fn from(from: ResolvableSrvRecord<C>) -> Self {
         Self::SrvRecord(from)
     }
 }
 
-impl<C: DnsClient> From<ResolvableNaptrRecord<C>> for ResolvableEnum<C> {
+impl<C: DnsClient + 'static> From<ResolvableNaptrRecord<C>> for ResolvableEnum<C> {
     fn from(from: ResolvableNaptrRecord<C>) -> Self {
         Self::NaptrRecord(from)
     }
@@ -1,6 +1,6 @@
 use crate::{
     resolvables::{Resolvable, ResolvableExt, ResolvableState},
-    Target,
+    Provenance, Target,
 };
 use async_trait::async_trait;
 use rsip::{Port, Transport};
@@ -11,6 +11,7 @@ pub struct ResolvableIpAddr {
     ip_addr: Resolvable<IpAddr>,
     port: Port,
     transport: Transport,
+    provenance: Provenance,
 }
 
 #[async_trait]
@@ -20,17 +21,27 @@ impl ResolvableExt<Target> for ResolvableIpAddr {
     }
 
     async fn resolve_next(&mut self) -> Option<Target> {
-        self.ip_addr.resolve_next().await.map(|ip_addr| Target {
-            ip_addr,
-            port: self.port,
-            transport: self.transport,
-        })
+        self.ip_addr
+            .resolve_next()
+            .await
+            .map(|ip_addr| Target::new(ip_addr, self.port, self.transport, self.provenance.clone()))
     }
 }
 
 impl ResolvableIpAddr {
     pub fn new(ip_addr: IpAddr, port: Port, transport: Transport) -> Self {
-        Self { ip_addr: Resolvable::non_empty(vec![ip_addr]), port, transport }
+        Self {
+            ip_addr: Resolvable::non_empty(vec![ip_addr]),
+            port,
+            transport,
+            provenance: Default::default(),
+        }
+    }
+
+    /// Sets the [Provenance] of the [Target] this resolvable yields.
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = provenance;
+        self
     }
 }
 
@@ -1,27 +1,30 @@
 use crate::{
-    records::NaptrFlags,
+    records::{NaptrEntry, NaptrFlags, SrvDomain},
     resolvables::{ResolvableExt, ResolvableSrvRecord, ResolvableState, ResolvableVec},
-    DnsClient, Target,
+    tracker::{SkipReason, TraceEvent, Tracker},
+    DnsClient, Provenance, ProvenanceStep, QueryType, Target,
 };
 use async_trait::async_trait;
 use rsip::{Domain, Transport};
-use std::convert::TryInto;
+use std::convert::TryFrom;
 
 #[derive(Debug, Clone)]
 pub struct ResolvableNaptrRecord<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     dns_client: C,
     domain: Domain,
     available_transports: Vec<Transport>,
+    tracker: Tracker,
+    provenance: Provenance,
     resolvable_srv_records: ResolvableVec<ResolvableSrvRecord<C>, Target>,
 }
 
 #[async_trait]
 impl<C> ResolvableExt<Target> for ResolvableNaptrRecord<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     fn state(&self) -> ResolvableState {
         self.resolvable_srv_records.state()
@@ -38,22 +41,56 @@ where
 
 impl<C> ResolvableNaptrRecord<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     pub fn new(dns_client: C, domain: Domain, available_transports: Vec<Transport>) -> Self {
         Self {
             dns_client,
             domain,
             available_transports,
+            tracker: Default::default(),
+            provenance: Default::default(),
             resolvable_srv_records: Default::default(),
         }
     }
 
-    //TODO: should probably resolve U + sip URI and A flag as well ?
+    /// Shares the given [Tracker] with this resolvable (and any resolvable it creates).
+    pub fn with_tracker(mut self, tracker: Tracker) -> Self {
+        self.tracker = tracker;
+        self
+    }
+
+    /// Sets the [Provenance] that led to this resolvable. The NAPTR step is appended to it for
+    /// each NAPTR entry followed.
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
     async fn resolve_domain(&mut self) {
-        use crate::SrvDomain;
+        self.resolve_srv_records().await;
+        if matches!(self.resolvable_srv_records.state(), ResolvableState::Empty) {
+            self.tracker.branch_empty(QueryType::Naptr, &self.domain);
+        }
+    }
+
+    //TODO: should probably resolve U + sip URI and A flag as well ?
+    async fn resolve_srv_records(&mut self) {
+        let query = self.dns_client.naptr_lookup(self.domain.clone());
+        let naptr_record =
+            match self.tracker.query(TraceEvent::NaptrQuery(self.domain.clone()), query).await {
+                Some(naptr_record) => naptr_record,
+                None => {
+                    self.resolvable_srv_records = ResolvableVec::empty();
+                    return;
+                }
+            };
+        self.tracker.push(TraceEvent::NaptrAnswer {
+            domain: self.domain.clone(),
+            record: naptr_record.clone(),
+        });
 
-        let naptr_record = match self.dns_client.naptr_lookup(self.domain.clone()).await {
+        let naptr_record = match naptr_record {
             Some(naptr_record) => naptr_record,
             None => {
                 self.resolvable_srv_records = ResolvableVec::empty();
@@ -62,16 +99,47 @@ where
         };
 
         let resolvable_srv_records = naptr_record
-            .into_iter()
-            .filter(|s| match s.services.transport() {
-                Some(transport) => self.available_transports.contains(&transport),
-                None => false,
+            .iter()
+            .filter_map(|entry| match self.srv_domain_for(entry) {
+                Ok(srv_domain) => Some((entry, srv_domain)),
+                Err(reason) => {
+                    self.tracker
+                        .push(TraceEvent::NaptrEntrySkipped { entry: entry.clone(), reason });
+                    None
+                }
+            })
+            .map(|(entry, srv_domain)| {
+                let preresolved = naptr_record.srv_record_for(&srv_domain);
+                let provenance = self.provenance.with(ProvenanceStep::Naptr {
+                    domain: self.domain.clone(),
+                    services: entry.services.clone(),
+                    replacement: srv_domain.clone(),
+                });
+                let resolvable = ResolvableSrvRecord::new(self.dns_client.clone(), srv_domain)
+                    .with_tracker(self.tracker.clone())
+                    .with_provenance(provenance);
+
+                match preresolved {
+                    Some(srv_record) => resolvable.with_preresolved(srv_record),
+                    None => resolvable,
+                }
             })
-            .filter(|s| matches!(s.flags, NaptrFlags::S))
-            .filter_map(|e| TryInto::<SrvDomain>::try_into(e).ok())
-            .map(|srv_domain| ResolvableSrvRecord::new(self.dns_client.clone(), srv_domain))
             .collect::<Vec<ResolvableSrvRecord<C>>>();
 
-        self.resolvable_srv_records = ResolvableVec::non_empty(resolvable_srv_records)
+        self.resolvable_srv_records =
+            ResolvableVec::non_empty(resolvable_srv_records).with_prefetch(self.tracker.prefetch())
+    }
+
+    fn srv_domain_for(&self, entry: &NaptrEntry) -> Result<SrvDomain, SkipReason> {
+        match entry.services.transport() {
+            Some(transport) if self.available_transports.contains(&transport) => (),
+            _ => return Err(SkipReason::UnsupportedService),
+        };
+
+        if !matches!(entry.flags, NaptrFlags::S) {
+            return Err(SkipReason::UnsupportedFlags);
+        }
+
+        SrvDomain::try_from(entry.clone()).map_err(|_| SkipReason::InvalidReplacement)
     }
 }
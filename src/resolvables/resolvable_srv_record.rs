@@ -1,24 +1,55 @@
 use crate::{
-    records::SrvDomain,
-    resolvables::{ResolvableAddrRecord, ResolvableExt, ResolvableState, ResolvableVec},
-    DnsClient, Target,
+    records::{SrvDomain, SrvRecord},
+    resolvables::{
+        Prefetched, ResolvableAddrRecord, ResolvableExt, ResolvableState, ResolvableVec,
+    },
+    tracker::{SkipReason, TraceEvent, Tracker},
+    DnsClient, Provenance, ProvenanceStep, QueryType, Target,
 };
 use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt};
+
+/// How the entries of a SRV record are ordered before their targets are tried.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub enum SrvSelection {
+    /// The entries are tried in the order the [DnsClient] returned them.
+    #[default]
+    AsReturned,
+    /// The entries are ordered by priority and, among the same priority, by weighted rendezvous
+    /// hashing on the given key (like the `Call-ID`), so that all requests with the same key
+    /// reach the same target while different keys spread across the targets in proportion to
+    /// their weight. See [SrvRecord::rendezvous_sorted].
+    Rendezvous(String),
+}
+
+impl SrvSelection {
+    fn apply(&self, srv_record: SrvRecord) -> SrvRecord {
+        match self {
+            Self::AsReturned => srv_record,
+            Self::Rendezvous(key) => srv_record.rendezvous_sorted(key),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ResolvableSrvRecord<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     dns_client: C,
     domain: SrvDomain,
+    preresolved: Option<SrvRecord>,
+    selection: Option<SrvSelection>,
+    prefetched: Option<Prefetched<Option<SrvRecord>>>,
+    tracker: Tracker,
+    provenance: Provenance,
     resolvable_addr_records: ResolvableVec<ResolvableAddrRecord<C>, Target>,
 }
 
 #[async_trait]
 impl<C> ResolvableExt<Target> for ResolvableSrvRecord<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     fn state(&self) -> ResolvableState {
         self.resolvable_addr_records.state()
@@ -31,33 +62,176 @@ where
 
         self.resolvable_addr_records.resolve_next().await
     }
+
+    //prefetches the SRV query and then the A/AAAA queries of the targets it gets, as needed by
+    //the window of the lookup
+    async fn prefetch(&mut self) {
+        if self.resolvable_addr_records.is_unset() {
+            if self.preresolved.is_none() {
+                let prefetched = match &self.prefetched {
+                    Some(prefetched) => prefetched.clone(),
+                    None => {
+                        let prefetched = self.query().shared();
+                        self.prefetched = Some(prefetched.clone());
+                        prefetched
+                    }
+                };
+                prefetched.await;
+            }
+
+            //the answer is already there, hence this doesn't wait for anything
+            self.resolve_domain().await;
+        }
+
+        self.resolvable_addr_records.prefetch().await;
+    }
 }
 
 impl<C> ResolvableSrvRecord<C>
 where
-    C: DnsClient,
+    C: DnsClient + 'static,
 {
     pub fn new(dns_client: C, domain: SrvDomain) -> Self {
-        Self { dns_client, domain, resolvable_addr_records: Default::default() }
+        Self {
+            dns_client,
+            domain,
+            preresolved: None,
+            selection: None,
+            prefetched: None,
+            tracker: Default::default(),
+            provenance: Default::default(),
+            resolvable_addr_records: Default::default(),
+        }
+    }
+
+    /// Shares the given [Tracker] with this resolvable (and any resolvable it creates).
+    pub fn with_tracker(mut self, tracker: Tracker) -> Self {
+        self.tracker = tracker;
+        self
+    }
+
+    /// Sets the [Provenance] that led to this resolvable (like a NAPTR entry). The SRV step is
+    /// appended to it for each SRV entry followed.
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
+    /// Sets how the SRV entries are ordered, instead of using the selection of the shared
+    /// [Tracker].
+    pub fn with_selection(mut self, selection: SrvSelection) -> Self {
+        self.selection = Some(selection);
+        self
     }
 
+    /// Uses the given (pre-resolved) SRV record instead of querying the dns client, for
+    /// instance when the SRV record came along in the additional section of a NAPTR answer.
+    pub fn with_preresolved(mut self, srv_record: SrvRecord) -> Self {
+        self.preresolved = Some(srv_record);
+        self
+    }
+
+    //the query is owned ('static), so that it can be kept in flight when prefetched,
+    //None means that the limits of the lookup didn't allow it
+    fn query(&self) -> BoxFuture<'static, Option<Option<SrvRecord>>> {
+        let dns_client = self.dns_client.clone();
+        let tracker = self.tracker.clone();
+        let domain = self.domain.clone();
+
+        async move {
+            let query = dns_client.srv_lookup(domain.clone());
+            let srv_record = tracker.query(TraceEvent::SrvQuery(domain.clone()), query).await?;
+            tracker.push(TraceEvent::SrvAnswer { domain, record: srv_record.clone() });
+            Some(srv_record)
+        }
+        .boxed()
+    }
+
+    //prefetching resolves the domain as well, hence the empty branch is reported here
     async fn resolve_domain(&mut self) {
-        match self.dns_client.srv_lookup(self.domain.clone()).await {
+        self.resolve_addr_records().await;
+        if matches!(self.resolvable_addr_records.state(), ResolvableState::Empty) {
+            self.tracker.branch_empty(QueryType::Srv, &self.domain);
+        }
+    }
+
+    async fn resolve_addr_records(&mut self) {
+        //SRV records that don't come from a NAPTR entry are branches of the lookup on their own
+        #[cfg(feature = "tracing")]
+        if self.provenance.steps().is_empty() {
+            crate::instrumentation::srv_branch_started(self.tracker.span(), &self.domain);
+        }
+
+        let srv_record = match self.preresolved.take() {
             Some(srv_record) => {
+                self.tracker.push(TraceEvent::SrvPreresolved(srv_record.clone()));
+                Some(srv_record)
+            }
+            None => {
+                let query = match self.prefetched.take() {
+                    Some(prefetched) => prefetched.await,
+                    None => self.query().await,
+                };
+
+                match query {
+                    Some(srv_record) => srv_record,
+                    None => {
+                        self.resolvable_addr_records = ResolvableVec::empty();
+                        return;
+                    }
+                }
+            }
+        };
+
+        match srv_record {
+            Some(srv_record) if srv_record.is_unavailable() => {
+                self.tracker.push(TraceEvent::SrvUnavailable(self.domain.clone()));
+                self.resolvable_addr_records = ResolvableVec::empty();
+            }
+            Some(srv_record) => {
+                let srv_record = match self.selection.clone() {
+                    Some(selection) => selection,
+                    None => self.tracker.srv_selection(),
+                }
+                .apply(srv_record);
                 let resolvable_addr_records = srv_record
-                    .domains_with_ports()
-                    .into_iter()
-                    .map(|(domain, port)| {
-                        ResolvableAddrRecord::new(
+                    .entries
+                    .iter()
+                    .filter(|entry| match entry.has_root_target() {
+                        true => {
+                            self.tracker.push(TraceEvent::SrvEntrySkipped {
+                                entry: (*entry).clone(),
+                                reason: SkipReason::RootTarget,
+                            });
+                            false
+                        }
+                        false => true,
+                    })
+                    .map(|entry| {
+                        let resolvable = ResolvableAddrRecord::new(
                             self.dns_client.clone(),
-                            domain,
-                            port,
+                            entry.target.clone(),
+                            entry.port,
                             srv_record.transport(),
                         )
+                        .with_tracker(self.tracker.clone())
+                        .with_provenance(self.provenance.with(ProvenanceStep::Srv {
+                            domain: srv_record.domain.clone(),
+                            priority: entry.priority,
+                            weight: entry.weight,
+                            port: entry.port,
+                            target: entry.target.clone(),
+                        }));
+
+                        match srv_record.addr_record_for(&entry.target) {
+                            Some(addr_record) => resolvable.with_preresolved(addr_record),
+                            None => resolvable,
+                        }
                     })
                     .collect::<Vec<_>>();
 
                 self.resolvable_addr_records = ResolvableVec::non_empty(resolvable_addr_records)
+                    .with_prefetch(self.tracker.prefetch())
             }
             None => {
                 self.resolvable_addr_records = ResolvableVec::empty();
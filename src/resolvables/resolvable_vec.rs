@@ -1,9 +1,15 @@
 use crate::resolvables::{ResolvableExt, ResolvableItem, ResolvableState};
 use async_trait::async_trait;
+use futures::future::{self, FutureExt};
 use std::{collections::VecDeque, marker::PhantomData};
 
+/// Resolves its items in order, moving to the next one once the current one is exhausted.
+///
+/// With a prefetch window (see [ResolvableVec::with_prefetch]), the next items of the window
+/// [prefetch](ResolvableExt::prefetch) their queries while the current one is being resolved.
+/// The items are still yielded in the same order.
 #[derive(Debug, Clone)]
-pub struct ResolvableVec<T, I>(Option<VecDeque<T>>, PhantomData<I>)
+pub struct ResolvableVec<T, I>(Option<VecDeque<T>>, usize, PhantomData<I>)
 where
     T: ResolvableExt<I> + std::marker::Send,
     I: ResolvableItem;
@@ -25,7 +31,46 @@ where
     }
 
     async fn resolve_next(&mut self) -> Option<I> {
-        self.0.resolve_next().await
+        let window = self.1;
+        let items = match (&mut self.0, window) {
+            (Some(items), window) if window > 0 => items,
+            _ => return self.0.resolve_next().await,
+        };
+
+        loop {
+            let next = {
+                let mut iter = items.iter_mut();
+                let mut current = iter.next()?.resolve_next();
+                let mut prefetches =
+                    future::join_all(iter.take(window).map(|item| item.prefetch())).fuse();
+
+                //the current item is polled first, so that queries are sent in the same order as
+                //in the lazy mode, and the prefetches are polled even if it's ready right away
+                future::poll_fn(|cx| {
+                    let next = current.poll_unpin(cx);
+                    let _ = prefetches.poll_unpin(cx);
+                    next
+                })
+                .await
+            };
+
+            match next {
+                Some(next) => return Some(next),
+                None => {
+                    items.pop_front();
+                }
+            }
+        }
+    }
+
+    //prefetches the current item and the ones in the window after it
+    async fn prefetch(&mut self) {
+        let window = self.1;
+
+        if let Some(items) = &mut self.0 {
+            let items = items.iter_mut().take(window.saturating_add(1));
+            future::join_all(items.map(|item| item.prefetch())).await;
+        }
     }
 }
 
@@ -35,15 +80,26 @@ where
     I: ResolvableItem,
 {
     pub fn unset() -> Self {
-        Self(None, Default::default())
+        Self(None, 0, Default::default())
     }
 
     pub fn empty() -> Self {
-        Self(Some(vec![].into()), Default::default())
+        Self(Some(vec![].into()), 0, Default::default())
     }
 
     pub fn non_empty(stuff: impl Into<VecDeque<T>>) -> Self {
-        Self(Some(stuff.into()), Default::default())
+        Self(Some(stuff.into()), 0, Default::default())
+    }
+
+    /// Sets how many of the items after the current one prefetch their queries. The default, 0,
+    /// keeps this resolvable lazy.
+    pub fn with_prefetch(mut self, window: usize) -> Self {
+        self.1 = window;
+        self
+    }
+
+    pub(crate) fn window(&self) -> usize {
+        self.1
     }
 }
 
@@ -53,7 +109,7 @@ where
     I: ResolvableItem,
 {
     fn default() -> Self {
-        Self(None, Default::default())
+        Self(None, 0, Default::default())
     }
 }
 
@@ -63,7 +119,7 @@ where
     I: ResolvableItem,
 {
     fn from(from: Vec<T>) -> Self {
-        Self(Some(from.into()), Default::default())
+        Self(Some(from.into()), 0, Default::default())
     }
 }
 
@@ -73,6 +129,6 @@ where
     I: ResolvableItem,
 {
     fn from(from: VecDeque<T>) -> Self {
-        Self(Some(from), Default::default())
+        Self(Some(from), 0, Default::default())
     }
 }
@@ -0,0 +1,168 @@
+//! Serde support, behind the `serde` feature.
+//!
+//! The representation is meant to be human-readable and stable, so that it can be stored or
+//! written by hand (like in a TOML config): domains, transports, NAPTR services/flags and SRV
+//! domains are strings in their DNS/SIP notation (`example.com`, `TLS`, `SIPS+D2T`,
+//! `_sips._tcp.example.com`), ports are numbers and ip addresses are strings.
+//!
+//! The `rsip` types don't implement serde themselves, hence the `with` modules here.
+
+use crate::{
+    records::{NaptrFlags, NaptrServices},
+    SrvDomain, SupportedTransports,
+};
+use rsip::{Domain, Port, Transport};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryFrom;
+
+pub(crate) mod domain {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(domain: &Domain, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(domain)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Domain, D::Error> {
+        String::deserialize(deserializer).map(Domain::from)
+    }
+}
+
+pub(crate) mod option_domain {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        domain: &Option<Domain>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match domain {
+            Some(domain) => serializer.collect_str(domain),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Domain>, D::Error> {
+        Ok(Option::<String>::deserialize(deserializer)?.map(Domain::from))
+    }
+}
+
+//the answer of a CNAME query, as recorded by the RecordingDnsClient
+pub(crate) mod cname_answer {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        answer: &Result<Option<Domain>, String>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        answer.as_ref().map(|domain| domain.as_ref().map(ToString::to_string)).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Result<Option<Domain>, String>, D::Error> {
+        Ok(Result::<Option<String>, String>::deserialize(deserializer)?
+            .map(|domain| domain.map(Domain::from)))
+    }
+}
+
+pub(crate) mod port {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(port: &Port, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16((*port).into())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Port, D::Error> {
+        u16::deserialize(deserializer).map(Port::from)
+    }
+}
+
+pub(crate) mod transport {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        transport: &Transport,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(transport)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Transport, D::Error> {
+        let transport = String::deserialize(deserializer)?;
+
+        transport.parse().map_err(|_| D::Error::custom(format!("unknown transport: {}", transport)))
+    }
+}
+
+//NAPTR regexps are character strings, invalid UTF-8 is replaced when serialized
+pub(crate) mod regexp {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(regexp: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&String::from_utf8_lossy(regexp))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        String::deserialize(deserializer).map(String::into_bytes)
+    }
+}
+
+impl Serialize for SrvDomain {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SrvDomain {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let srv_domain = String::deserialize(deserializer)?;
+
+        SrvDomain::try_from(srv_domain.as_str())
+            .map_err(|_| D::Error::custom(format!("invalid SRV domain: {}", srv_domain)))
+    }
+}
+
+impl Serialize for NaptrServices {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for NaptrServices {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let services = String::deserialize(deserializer)?;
+
+        Ok(NaptrServices::try_from(services.as_bytes()).unwrap_or(NaptrServices::Other(services)))
+    }
+}
+
+impl Serialize for NaptrFlags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&String::from_utf8_lossy(self.as_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for NaptrFlags {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|flags| NaptrFlags::from(flags.as_bytes()))
+    }
+}
+
+//a list of transports, like ["UDP", "TLS"]
+impl Serialize for SupportedTransports {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.all().iter().map(ToString::to_string))
+    }
+}
+
+impl<'de> Deserialize<'de> for SupportedTransports {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Item(#[serde(with = "transport")] Transport);
+
+        let transports = Vec::<Item>::deserialize(deserializer)?;
+
+        Ok(SupportedTransports::only(transports.into_iter().map(|item| item.0).collect()))
+    }
+}
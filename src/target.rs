@@ -1,15 +1,45 @@
-use rsip::{Port, Transport};
+use crate::records::{NaptrServices, SrvDomain};
+use rsip::{Domain, Port, Transport};
 use std::net::{IpAddr, SocketAddr};
 
 /// The (ip, port, transport) tuple resolved that should be used as the next peer target.
+///
+/// `provenance` explains which NAPTR/SRV/A branch of the [Lookup](super::Lookup) produced this
+/// target.
+///
+/// For secure transports (TLS, TLS-SCTP and WSS), `server_name` holds the domain that the peer
+/// certificate should be validated against. According to
+/// [RFC 5922](https://datatracker.ietf.org/doc/html/rfc5922#section-4) that's the domain of the
+/// original URI, not the target host of a SRV record, which is kept in `srv_target` instead. Both
+/// are `None` for insecure transports and `server_name` is `None` when the URI host is an ip
+/// address.
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Target {
     pub ip_addr: IpAddr,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::port"))]
     pub port: Port,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::transport"))]
     pub transport: Transport,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::option_domain"))]
+    pub server_name: Option<Domain>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::option_domain"))]
+    pub srv_target: Option<Domain>,
+    pub provenance: Provenance,
 }
 
 impl Target {
+    /// Creates a target out of the [Provenance] that led to it, from which the `server_name` and
+    /// `srv_target` are taken when the transport is secure.
+    pub fn new(ip_addr: IpAddr, port: Port, transport: Transport, provenance: Provenance) -> Self {
+        let (server_name, srv_target) = match transport.is_secure() {
+            true => (provenance.origin().cloned(), provenance.srv_target().cloned()),
+            false => (None, None),
+        };
+
+        Self { ip_addr, port, transport, server_name, srv_target, provenance }
+    }
+
     pub fn socket_addr(&self) -> SocketAddr {
         SocketAddr::from((self.ip_addr, self.port.into()))
     }
@@ -19,6 +49,114 @@ impl From<(IpAddr, Port, Transport)> for Target {
     fn from(from: (IpAddr, Port, Transport)) -> Target {
         let (ip_addr, port, transport) = from;
 
-        Target { ip_addr, port, transport }
+        Target::new(ip_addr, port, transport, Default::default())
+    }
+}
+
+/// The chain of DNS answers that led to a [Target], in the order they were followed.
+///
+/// Its `Display` implementation gives a compact one line representation, suitable for logs or
+/// SIP headers, like:
+/// `NAPTR example.com SIPS+D2T -> SRV _sips._tcp.example.com priority 10 weight 5 sip1.example.com:5061 -> A sip1.example.com 1.2.3.4`
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Provenance(Vec<ProvenanceStep>);
+
+/// A single step of a [Provenance].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ProvenanceStep {
+    /// The ip address was given, no DNS query was involved.
+    IpAddr(IpAddr),
+    /// A NAPTR entry of `domain` with the given services pointed to the `replacement`.
+    Naptr {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialization::domain"))]
+        domain: Domain,
+        services: NaptrServices,
+        replacement: SrvDomain,
+    },
+    /// A SRV entry of `domain` pointed to the `target` host and `port`.
+    Srv {
+        domain: SrvDomain,
+        priority: u16,
+        weight: u16,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialization::port"))]
+        port: Port,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialization::domain"))]
+        target: Domain,
+    },
+    /// An A or AAAA lookup of `domain` returned the `ip_addr`.
+    Addr {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialization::domain"))]
+        domain: Domain,
+        ip_addr: IpAddr,
+    },
+}
+
+impl Provenance {
+    pub fn steps(&self) -> &[ProvenanceStep] {
+        self.0.as_slice()
+    }
+
+    /// Returns the domain the lookup started from, that is the domain of the first NAPTR, SRV or
+    /// A/AAAA step. Returns `None` if the first step is an ip address (or there are no steps).
+    pub fn origin(&self) -> Option<&Domain> {
+        match self.0.first()? {
+            ProvenanceStep::IpAddr(_) => None,
+            ProvenanceStep::Naptr { domain, .. } => Some(domain),
+            ProvenanceStep::Srv { domain, .. } => Some(&domain.domain),
+            ProvenanceStep::Addr { domain, .. } => Some(domain),
+        }
+    }
+
+    /// Returns the target host of the SRV step, if any.
+    pub fn srv_target(&self) -> Option<&Domain> {
+        self.0.iter().find_map(|step| match step {
+            ProvenanceStep::Srv { target, .. } => Some(target),
+            _ => None,
+        })
+    }
+
+    /// Returns a new provenance, with the given step appended to the current ones.
+    pub fn with(&self, step: ProvenanceStep) -> Self {
+        let mut steps = self.0.clone();
+        steps.push(step);
+
+        Self(steps)
+    }
+}
+
+impl From<Vec<ProvenanceStep>> for Provenance {
+    fn from(from: Vec<ProvenanceStep>) -> Self {
+        Self(from)
+    }
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let steps = self.0.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        write!(f, "{}", steps.join(" -> "))
+    }
+}
+
+impl std::fmt::Display for ProvenanceStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IpAddr(ip_addr) => write!(f, "IP {}", ip_addr),
+            Self::Naptr { domain, services, .. } => write!(f, "NAPTR {} {}", domain, services),
+            Self::Srv { domain, priority, weight, port, target } => write!(
+                f,
+                "SRV {} priority {} weight {} {}:{}",
+                domain, priority, weight, target, port
+            ),
+            Self::Addr { domain, ip_addr: IpAddr::V4(ip_addr) } => {
+                write!(f, "A {} {}", domain, ip_addr)
+            }
+            Self::Addr { domain, ip_addr: IpAddr::V6(ip_addr) } => {
+                write!(f, "AAAA {} {}", domain, ip_addr)
+            }
+        }
     }
 }
@@ -0,0 +1,216 @@
+use crate::{resolvables::ResolvableExt, DnsClient, Lookup, Target};
+use futures::lock::Mutex as AsyncMutex;
+use rsip::{prelude::*, Error, Request};
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Identifies a client transaction: the `branch` parameter of the top `Via` header along with the
+/// `Call-ID`. An ACK for a non-2xx response and a CANCEL share both with the INVITE they belong
+/// to, hence they map to the same key.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TransactionKey {
+    pub branch: String,
+    pub call_id: String,
+}
+
+impl TransactionKey {
+    pub fn new(branch: impl Into<String>, call_id: impl Into<String>) -> Self {
+        Self { branch: branch.into(), call_id: call_id.into() }
+    }
+}
+
+impl TryFrom<&Request> for TransactionKey {
+    type Error = Error;
+
+    fn try_from(request: &Request) -> Result<Self, Self::Error> {
+        let branch = request
+            .via_header()?
+            .typed()?
+            .branch()
+            .map(|branch| branch.to_string())
+            .ok_or_else(|| Error::Unexpected("missing branch in Via header".into()))?;
+
+        Ok(Self { branch, call_id: request.call_id_header()?.value().into() })
+    }
+}
+
+/// Keeps the [Target] picked for each transaction, so that follow-up requests (like an ACK for
+/// a non-2xx response or a CANCEL) go to the same target as the request they belong to, as
+/// [RFC 3263 section 4.3](https://datatracker.ietf.org/doc/html/rfc3263#section-4.3) requires.
+///
+/// The memo holds the [Lookup] of each transaction and moves to its next target only when the
+/// current one is reported as failed, using [TargetMemo::report_failure]. Entries expire after a
+/// configurable time since they were created ([TargetMemo::DEFAULT_TTL] by default, which
+/// matches the SIP transaction timer B). Expired entries are dropped lazily, on any access, in
+/// the order they expire, so that an access doesn't go through all the entries.
+///
+/// Cloning the memo is cheap and the clone shares the same entries.
+#[derive(Debug, Clone)]
+pub struct TargetMemo<C: DnsClient + 'static> {
+    ttl: Duration,
+    entries: Arc<Mutex<Entries<C>>>,
+}
+
+type SharedEntry<C> = Arc<AsyncMutex<Entry<C>>>;
+
+#[derive(Debug)]
+struct Entries<C: DnsClient + 'static> {
+    by_key: HashMap<TransactionKey, (SharedEntry<C>, Instant)>,
+    //the keys by expiration time, which is also their insertion order as the ttl is fixed
+    expirations: VecDeque<(Instant, TransactionKey)>,
+}
+
+impl<C: DnsClient + 'static> Default for Entries<C> {
+    fn default() -> Self {
+        Self { by_key: Default::default(), expirations: Default::default() }
+    }
+}
+
+impl<C: DnsClient + 'static> Entries<C> {
+    //drops the expired entries, skipping (for now) the ones in use
+    fn expire(&mut self, now: Instant) {
+        let mut in_use = vec![];
+
+        while let Some((expires_at, key)) = self.expirations.front().cloned() {
+            if expires_at > now {
+                break;
+            }
+            self.expirations.pop_front();
+
+            match self.by_key.get(&key) {
+                //the key was removed and created again since, its entry expires later
+                Some((_, entry_expires_at)) if *entry_expires_at != expires_at => (),
+                Some((entry, _)) if entry.try_lock().is_none() => in_use.push((expires_at, key)),
+                Some(_) => {
+                    self.by_key.remove(&key);
+                }
+                None => (),
+            }
+        }
+
+        for expiration in in_use.into_iter().rev() {
+            self.expirations.push_front(expiration);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry<C: DnsClient + 'static> {
+    lookup: Lookup<C>,
+    target: Option<Target>,
+    resolved: bool,
+}
+
+impl<C: DnsClient + 'static> Entry<C> {
+    async fn next_target(&mut self) -> Option<Target> {
+        self.target = self.lookup.resolve_next().await;
+        self.resolved = true;
+        self.target.clone()
+    }
+}
+
+impl<C: DnsClient + 'static> TargetMemo<C> {
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(32);
+
+    pub fn new() -> Self {
+        Self { ttl: Self::DEFAULT_TTL, entries: Default::default() }
+    }
+
+    /// Sets for how long an entry is kept after it's created.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Returns the target pinned for the transaction. The first time a transaction is seen, its
+    /// lookup is created out of the given one (usually a [Context](crate::Context)) and resolved
+    /// to its first target, otherwise the given lookup is not used at all.
+    ///
+    /// Returns `None` if the lookup has no (more) targets.
+    pub async fn target_for(
+        &self,
+        key: &TransactionKey,
+        lookup: impl Into<Lookup<C>>,
+    ) -> Option<Target> {
+        let entry = {
+            let mut entries = self.lock();
+            let Entries { by_key, expirations } = &mut *entries;
+            let (entry, _) = by_key.entry(key.clone()).or_insert_with(|| {
+                let expires_at = Instant::now() + self.ttl;
+                expirations.push_back((expires_at, key.clone()));
+                let entry = Entry { lookup: lookup.into(), target: None, resolved: false };
+
+                (Arc::new(AsyncMutex::new(entry)), expires_at)
+            });
+
+            entry.clone()
+        };
+
+        let mut entry = entry.lock().await;
+        match entry.resolved {
+            true => entry.target.clone(),
+            false => entry.next_target().await,
+        }
+    }
+
+    /// Returns the target pinned for the transaction, if it's known.
+    pub async fn pinned(&self, key: &TransactionKey) -> Option<Target> {
+        let entry = self.entry(key)?;
+        let entry = entry.lock().await;
+        entry.target.clone()
+    }
+
+    /// Reports that the pinned target of the transaction failed, moving to the next target of its
+    /// lookup, which is returned and pinned from now on. Returns `None` if the transaction is not
+    /// known or its lookup has no more targets.
+    pub async fn report_failure(&self, key: &TransactionKey) -> Option<Target> {
+        let entry = self.entry(key)?;
+        let mut entry = entry.lock().await;
+        entry.next_target().await
+    }
+
+    /// Reports that the pinned target of the transaction worked, see
+    /// [Lookup::report_success](crate::Lookup::report_success).
+    pub async fn report_success(&self, key: &TransactionKey) {
+        if let Some(entry) = self.entry(key) {
+            entry.lock().await.lookup.report_success();
+        }
+    }
+
+    /// Forgets the transaction, for instance once it's terminated.
+    pub fn remove(&self, key: &TransactionKey) {
+        //its expiration is dropped once due, as it no longer matches any entry
+        self.lock().by_key.remove(key);
+    }
+
+    /// Returns the number of (non expired) transactions in the memo.
+    pub fn len(&self) -> usize {
+        self.lock().by_key.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn entry(&self, key: &TransactionKey) -> Option<SharedEntry<C>> {
+        self.lock().by_key.get(key).map(|(entry, _)| entry.clone())
+    }
+
+    //also drops the expired entries
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries<C>> {
+        let mut entries = self.entries.lock().expect("target memo lock poisoned");
+        entries.expire(Instant::now());
+
+        entries
+    }
+}
+
+impl<C: DnsClient + 'static> Default for TargetMemo<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,442 @@
+use crate::{
+    observer::Answer,
+    records::{AddrRecord, NaptrEntry, NaptrRecord, SrvDomain, SrvEntry, SrvRecord},
+    resolvables::SrvSelection,
+    Limits, Observer, QueryOutcome, QueryType, Target,
+};
+use futures::future::{self, Either, Future};
+use futures_timer::Delay;
+use rsip::{Domain, Error, Transport};
+use std::{
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+    time::{Duration, Instant},
+};
+
+/// Shared state of a [Lookup](super::Lookup). The same tracker is handed to every resolvable
+/// of the lookup tree, so anything that happens deep in the tree (like a SRV record signaling
+/// that a service is not available) can be observed at the top, through
+/// [Lookup::trace](super::Lookup::trace).
+///
+/// The tracker also enforces the [Limits] of the lookup, as every resolvable asks it before
+/// sending a query.
+///
+/// Cloning a tracker is cheap and the clone shares the same state.
+#[derive(Debug, Clone, Default)]
+pub struct Tracker {
+    events: Arc<Mutex<Vec<TraceEvent>>>,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    queries_left: Option<usize>,
+    deadline: Option<Instant>,
+    stop_reason: Option<StopReason>,
+    prefetch: usize,
+    srv_selection: SrvSelection,
+    max_in_flight: Option<usize>,
+    in_flight: usize,
+    waiting: Vec<Waker>,
+    observer: Option<Arc<dyn Observer>>,
+    targets_yielded: usize,
+    exhausted: bool,
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::Span>,
+}
+
+/// Things that happened during a [Lookup](super::Lookup): every query sent to the
+/// [DnsClient](super::DnsClient) along with its answer, every branch that was skipped (and why)
+/// and every [Target] that was yielded.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TraceEvent {
+    NaptrQuery(Domain),
+    NaptrAnswer {
+        domain: Domain,
+        record: Option<NaptrRecord>,
+    },
+    SrvQuery(SrvDomain),
+    SrvAnswer {
+        domain: SrvDomain,
+        record: Option<SrvRecord>,
+    },
+    /// A SRV record came along with a NAPTR answer, hence no SRV query was needed.
+    SrvPreresolved(SrvRecord),
+    AddrQuery(Domain),
+    AddrAnswer {
+        domain: Domain,
+        record: Result<AddrRecord, Error>,
+    },
+    /// An address record came along with a SRV answer, hence no A/AAAA query was needed.
+    AddrPreresolved(AddrRecord),
+    NaptrEntrySkipped {
+        entry: NaptrEntry,
+        reason: SkipReason,
+    },
+    SrvEntrySkipped {
+        entry: SrvEntry,
+        reason: SkipReason,
+    },
+    /// The SRV lookup returned a single record with a `.` target, which according to
+    /// [RFC 2782](https://datatracker.ietf.org/doc/html/rfc2782) means that the service is
+    /// decidedly not available at that domain.
+    SrvUnavailable(SrvDomain),
+    /// The A/AAAA fallback for the domain and transport was skipped, because a SRV lookup for
+    /// the same domain and transport signaled that the service is not available.
+    AddrFallbackSkipped {
+        domain: Domain,
+        transport: Transport,
+    },
+    /// The [Target] was yielded by the lookup. Its provenance explains where it came from.
+    Target(Target),
+    /// The lookup reached one of its [Limits] and won't send any more queries.
+    Stopped(StopReason),
+}
+
+/// The reason a [Lookup](super::Lookup) stopped before trying every branch.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StopReason {
+    /// The lookup sent as many queries as [Limits::max_queries] allows.
+    QueryBudgetExhausted,
+    /// The lookup ran for longer than [Limits::deadline].
+    DeadlineExceeded,
+}
+
+/// The reason a NAPTR or SRV entry was not followed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SkipReason {
+    /// The NAPTR service is unknown or its transport is not among the available transports.
+    UnsupportedService,
+    /// The NAPTR flags are other than `S`.
+    UnsupportedFlags,
+    /// The NAPTR replacement is not a valid SRV domain.
+    InvalidReplacement,
+    /// The SRV target is the root domain (`.`).
+    RootTarget,
+}
+
+impl Tracker {
+    /// Creates a tracker that enforces the given limits. The deadline, if any, starts counting
+    /// from now.
+    pub fn with_limits(limits: &Limits) -> Self {
+        Self {
+            events: Default::default(),
+            state: Arc::new(Mutex::new(State {
+                queries_left: limits.max_queries,
+                deadline: limits.deadline.map(|deadline| Instant::now() + deadline),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// Sets the prefetch window of the resolvables that share this tracker (see
+    /// [Context::with_prefetch](super::Context::with_prefetch)).
+    pub fn with_prefetch(self, prefetch: usize) -> Self {
+        self.set_prefetch(prefetch);
+        self
+    }
+
+    pub(crate) fn prefetch(&self) -> usize {
+        self.state.lock().expect("tracker lock poisoned").prefetch
+    }
+
+    pub(crate) fn set_prefetch(&self, prefetch: usize) {
+        self.state.lock().expect("tracker lock poisoned").prefetch = prefetch;
+    }
+
+    /// Sets how the entries of the SRV records are ordered by the resolvables that share this
+    /// tracker (see [Context::with_srv_selection](super::Context::with_srv_selection)).
+    pub fn with_srv_selection(self, srv_selection: SrvSelection) -> Self {
+        self.state.lock().expect("tracker lock poisoned").srv_selection = srv_selection;
+        self
+    }
+
+    pub(crate) fn srv_selection(&self) -> SrvSelection {
+        self.state.lock().expect("tracker lock poisoned").srv_selection.clone()
+    }
+
+    pub(crate) fn max_in_flight(&self) -> Option<usize> {
+        self.state.lock().expect("tracker lock poisoned").max_in_flight
+    }
+
+    //caps the number of queries in flight at the same time, the rest wait for their turn
+    pub(crate) fn set_max_in_flight(&self, max_in_flight: Option<usize>) {
+        self.state.lock().expect("tracker lock poisoned").max_in_flight = max_in_flight;
+    }
+
+    /// Returns why the lookup stopped sending queries, if it did.
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.state.lock().expect("tracker lock poisoned").stop_reason
+    }
+
+    /// Returns all the events recorded so far, in the order they happened.
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.events.lock().expect("tracker lock poisoned").clone()
+    }
+
+    /// Sets the [Observer] of the resolvables that share this tracker (see
+    /// [Lookup::with_observer](super::Lookup::with_observer)).
+    pub fn with_observer(self, observer: Arc<dyn Observer>) -> Self {
+        self.set_observer(observer);
+        self
+    }
+
+    pub(crate) fn set_observer(&self, observer: Arc<dyn Observer>) {
+        self.state.lock().expect("tracker lock poisoned").observer = Some(observer);
+    }
+
+    fn observer(&self) -> Option<Arc<dyn Observer>> {
+        self.state.lock().expect("tracker lock poisoned").observer.clone()
+    }
+
+    pub(crate) fn branch_empty(&self, query_type: QueryType, domain: &dyn std::fmt::Display) {
+        if let Some(observer) = self.observer() {
+            observer.branch_empty(query_type, &domain.to_string());
+        }
+    }
+
+    pub(crate) fn target_yielded(&self, target: &Target, latency: Duration) {
+        let (observer, attempt) = {
+            let mut state = self.state.lock().expect("tracker lock poisoned");
+            state.targets_yielded += 1;
+            (state.observer.clone(), state.targets_yielded)
+        };
+
+        if let Some(observer) = observer {
+            observer.target_yielded(target, attempt, latency);
+        }
+    }
+
+    pub(crate) fn target_succeeded(&self) {
+        let (observer, attempt) = {
+            let state = self.state.lock().expect("tracker lock poisoned");
+            (state.observer.clone(), state.targets_yielded)
+        };
+
+        if let (Some(observer), true) = (observer, attempt > 0) {
+            observer.target_succeeded(attempt);
+        }
+    }
+
+    //reported once, the first time the lookup runs out of targets
+    pub(crate) fn exhausted(&self) {
+        let (observer, targets) = {
+            let mut state = self.state.lock().expect("tracker lock poisoned");
+            if std::mem::replace(&mut state.exhausted, true) {
+                return;
+            }
+            (state.observer.clone(), state.targets_yielded)
+        };
+
+        if let Some(observer) = observer {
+            observer.lookup_exhausted(targets);
+        }
+    }
+
+    //the span of the lookup that this tracker belongs to
+    #[cfg(feature = "tracing")]
+    pub(crate) fn set_span(&self, span: tracing::Span) {
+        self.state.lock().expect("tracker lock poisoned").span = Some(span);
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn span(&self) -> Option<tracing::Span> {
+        self.state.lock().expect("tracker lock poisoned").span.clone()
+    }
+
+    pub(crate) fn push(&self, event: TraceEvent) {
+        #[cfg(feature = "tracing")]
+        crate::instrumentation::on_event(self.span(), &event);
+        if let Some(observer) = self.observer() {
+            match &event {
+                TraceEvent::SrvPreresolved(record) => {
+                    observer.query_preresolved(QueryType::Srv, &record.domain.to_string())
+                }
+                TraceEvent::AddrPreresolved(record) => {
+                    observer.query_preresolved(QueryType::Addr, &record.domain.to_string())
+                }
+                _ => (),
+            }
+        }
+        self.events.lock().expect("tracker lock poisoned").push(event)
+    }
+
+    pub(crate) fn srv_unavailable(&self, domain: &Domain, transport: Transport) -> bool {
+        self.events.lock().expect("tracker lock poisoned").iter().any(|event| match event {
+            TraceEvent::SrvUnavailable(srv_domain) => {
+                &srv_domain.domain == domain && srv_domain.transport() == transport
+            }
+            _ => false,
+        })
+    }
+
+    //runs the query, recording the given event first, unless the limits don't allow it, in which
+    //case None is returned (same when the deadline is reached while the query is waiting for its
+    //turn or is in flight)
+    pub(crate) async fn query<F>(&self, event: TraceEvent, query: F) -> Option<F::Output>
+    where
+        F: Future,
+        F::Output: Answer,
+    {
+        let deadline = match self.start_query() {
+            Ok(deadline) => deadline,
+            Err(reason) => {
+                self.stop(reason);
+                return None;
+            }
+        };
+        let (query_type, domain) = event.query().expect("not a query event");
+        //reported once the query ends, the query is considered cancelled until it's set
+        let outcome = Mutex::new(None);
+        #[cfg(feature = "tracing")]
+        let span = crate::instrumentation::query_span(self.span(), query_type, &domain);
+        let query = async {
+            let _permit = self.permit().await;
+            self.push(event);
+            let _ended = QueryEnded::new(self.observer(), query_type, &domain, &outcome);
+            let answer = query.await;
+            #[cfg(feature = "tracing")]
+            crate::instrumentation::record_answer(&span, &answer, _ended.sent_at.elapsed());
+            *outcome.lock().expect("outcome lock poisoned") = Some(answer.outcome());
+
+            answer
+        };
+        #[cfg(feature = "tracing")]
+        let query = tracing::Instrument::instrument(query, span.clone());
+
+        match deadline {
+            Some(deadline) => {
+                let delay = Delay::new(deadline.saturating_duration_since(Instant::now()));
+
+                match future::select(Box::pin(query), delay).await {
+                    Either::Left((answer, _)) => Some(answer),
+                    Either::Right((_, query)) => {
+                        #[cfg(feature = "tracing")]
+                        crate::instrumentation::record_deadline_exceeded(&span);
+                        *outcome.lock().expect("outcome lock poisoned") =
+                            Some(QueryOutcome::DeadlineExceeded);
+                        drop(query);
+                        self.stop(StopReason::DeadlineExceeded);
+                        None
+                    }
+                }
+            }
+            None => Some(query.await),
+        }
+    }
+
+    async fn permit(&self) -> Permit<'_> {
+        future::poll_fn(|cx| {
+            let mut state = self.state.lock().expect("tracker lock poisoned");
+
+            match state.max_in_flight {
+                Some(max_in_flight) if state.in_flight >= max_in_flight => {
+                    state.waiting.push(cx.waker().clone());
+                    Poll::Pending
+                }
+                _ => {
+                    state.in_flight += 1;
+                    Poll::Ready(())
+                }
+            }
+        })
+        .await;
+
+        Permit(self)
+    }
+
+    fn start_query(&self) -> Result<Option<Instant>, StopReason> {
+        let mut state = self.state.lock().expect("tracker lock poisoned");
+
+        if let Some(reason) = state.stop_reason {
+            return Err(reason);
+        }
+        if matches!(state.deadline, Some(deadline) if deadline <= Instant::now()) {
+            return Err(StopReason::DeadlineExceeded);
+        }
+        match state.queries_left.as_mut() {
+            Some(0) => return Err(StopReason::QueryBudgetExhausted),
+            Some(queries_left) => *queries_left -= 1,
+            None => (),
+        }
+
+        Ok(state.deadline)
+    }
+
+    fn stop(&self, reason: StopReason) {
+        let mut state = self.state.lock().expect("tracker lock poisoned");
+
+        if state.stop_reason.is_none() {
+            state.stop_reason = Some(reason);
+            drop(state);
+            self.push(TraceEvent::Stopped(reason));
+        }
+    }
+}
+
+impl TraceEvent {
+    //the type and name of a query event
+    fn query(&self) -> Option<(QueryType, String)> {
+        match self {
+            Self::NaptrQuery(domain) => Some((QueryType::Naptr, domain.to_string())),
+            Self::SrvQuery(domain) => Some((QueryType::Srv, domain.to_string())),
+            Self::AddrQuery(domain) => Some((QueryType::Addr, domain.to_string())),
+            _ => None,
+        }
+    }
+}
+
+//a query sent to the dns client, reported to the observer once dropped, even if the query
+//future is dropped before the answer comes in
+struct QueryEnded<'a> {
+    observer: Option<Arc<dyn Observer>>,
+    query_type: QueryType,
+    domain: &'a str,
+    outcome: &'a Mutex<Option<QueryOutcome>>,
+    sent_at: Instant,
+}
+
+impl<'a> QueryEnded<'a> {
+    fn new(
+        observer: Option<Arc<dyn Observer>>,
+        query_type: QueryType,
+        domain: &'a str,
+        outcome: &'a Mutex<Option<QueryOutcome>>,
+    ) -> Self {
+        if let Some(observer) = &observer {
+            observer.query_started(query_type, domain);
+        }
+
+        Self { observer, query_type, domain, outcome, sent_at: Instant::now() }
+    }
+}
+
+impl Drop for QueryEnded<'_> {
+    fn drop(&mut self) {
+        let outcome = self.outcome.lock().expect("outcome lock poisoned").take();
+
+        if let Some(observer) = &self.observer {
+            observer.query_ended(
+                self.query_type,
+                self.domain,
+                outcome.unwrap_or(QueryOutcome::Cancelled),
+                self.sent_at.elapsed(),
+            );
+        }
+    }
+}
+
+//a query in flight, counted until dropped
+struct Permit<'a>(&'a Tracker);
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let waiting = {
+            let mut state = self.0.state.lock().expect("tracker lock poisoned");
+            state.in_flight -= 1;
+            std::mem::take(&mut state.waiting)
+        };
+
+        waiting.into_iter().for_each(Waker::wake);
+    }
+}
@@ -1,5 +1,4 @@
 use async_trait::async_trait;
-use std::{convert::TryInto, net::IpAddr};
 
 use crate::{records::*, DnsClient, SrvDomain};
 use trust_dns_proto::{rr::record_type::RecordType, xfer::dns_handle::DnsHandle};
@@ -36,34 +35,44 @@ where
     P: ConnectionProvider<Conn = C>,
 {
     async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
-        self.resolver
-            .lookup(domain.to_string(), RecordType::NAPTR, Default::default())
-            .await
-            .ok()
-            .map(|r| {
-                let entries = r
-                    .into_iter()
-                    .filter_map(|rdata| rdata.try_into().ok())
-                    .collect::<Vec<NaptrEntry>>();
-                NaptrRecord { domain, entries }
-            })
+        self.try_naptr_lookup(domain).await.ok().flatten()
     }
 
     async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
-        self.resolver.srv_lookup(domain.to_string()).await.ok().map(|r| {
-            let entries = r.into_iter().map(Into::into).collect::<Vec<SrvEntry>>();
-            SrvRecord { domain, entries }
-        })
+        self.try_srv_lookup(domain).await.ok().flatten()
     }
 
     async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
         self.resolver
             .lookup_ip(domain.to_string())
             .await
-            .map(|r| {
-                let ip_addrs = r.into_iter().map(Into::into).collect::<Vec<IpAddr>>();
-                AddrRecord { domain, ip_addrs }
-            })
+            .map(|r| super::addr_record_from(domain, r))
             .map_err(|e| Error::Unexpected(e.to_string()))
     }
+
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        let lookup =
+            self.resolver.lookup(domain.to_string(), RecordType::NAPTR, Default::default());
+
+        super::answer_from(lookup.await).map(|r| r.map(|r| super::naptr_record_from(domain, r)))
+    }
+
+    async fn try_srv_lookup(&self, domain: SrvDomain) -> Result<Option<SrvRecord>, Error> {
+        let lookup = self.resolver.srv_lookup(domain.to_string());
+
+        super::answer_from(lookup.await).map(|r| r.map(|r| super::srv_record_from(domain, r)))
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        let lookup = self.resolver.lookup_ip(domain.to_string());
+
+        super::answer_from(lookup.await).map(|r| r.map(|r| super::addr_record_from(domain, r)))
+    }
+
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        let lookup =
+            self.resolver.lookup(domain.to_string(), RecordType::CNAME, Default::default());
+
+        super::answer_from(lookup.await).map(|r| r.and_then(super::cname_from))
+    }
 }
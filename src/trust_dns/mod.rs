@@ -9,19 +9,41 @@
 //!
 //! In more advanced scenarios, you might want to build a custom dns client that will implement
 //! query caching etc.
+//!
+//! Of the additional section of the answers, only the A/AAAA records of SRV targets are used:
+//! the `trust-dns` resolver drops the SRV records that come along with a NAPTR answer, hence the
+//! NAPTR records of these clients never have any `additional_srvs` and their replacements are
+//! always queried.
+//!
+//! With the `test-utils` feature enabled, a [TestDnsServer](crate::TestDnsServer) is also
+//! provided: a small authoritative DNS server, serving an in-memory zone on localhost, that these
+//! clients can be pointed to in integration tests.
 
 mod async_trust_dns_client;
+#[cfg(feature = "test-utils")]
+mod test_dns_server;
 mod trust_dns_client;
 
 pub use async_trust_dns_client::AsyncTrustDnsClient;
+#[cfg(feature = "test-utils")]
+pub use test_dns_server::{TestDnsServer, TestZone};
 pub use trust_dns_client::TrustDnsClient;
 
 use std::convert::{TryFrom, TryInto};
+use std::net::IpAddr;
 
 use crate::records::*;
-use trust_dns_proto::rr::{rdata::srv::SRV, record_data::RData};
+use trust_dns_proto::{
+    op::ResponseCode,
+    rr::{rdata::srv::SRV, record_data::RData, resource::Record},
+};
+use trust_dns_resolver::{
+    error::{ResolveError, ResolveErrorKind},
+    lookup::{Lookup, SrvLookup},
+    lookup_ip::LookupIp,
+};
 
-use rsip::Error;
+use rsip::{Domain, Error};
 
 impl TryFrom<RData> for NaptrEntry {
     type Error = Error;
@@ -51,3 +73,68 @@ impl From<SRV> for SrvEntry {
         }
     }
 }
+
+//an answer without records (NXDOMAIN or NOERROR) is not a failure, anything else is, including
+//a SERVFAIL that trust-dns also reports as no records found
+pub(crate) fn answer_from<T>(result: Result<T, ResolveError>) -> Result<Option<T>, Error> {
+    match result {
+        Ok(answer) => Ok(Some(answer)),
+        Err(error) => match error.kind() {
+            ResolveErrorKind::NoRecordsFound {
+                response_code: ResponseCode::NXDomain | ResponseCode::NoError,
+                ..
+            } => Ok(None),
+            _ => Err(Error::Unexpected(error.to_string())),
+        },
+    }
+}
+
+//the resolver keeps only the records of the queried type (and, for SRV queries, the addresses of
+//the targets), so the SRV records that came along with a NAPTR answer are never available here
+pub(crate) fn naptr_record_from(domain: Domain, lookup: Lookup) -> NaptrRecord {
+    let entries =
+        lookup.into_iter().filter_map(|rdata| rdata.try_into().ok()).collect::<Vec<NaptrEntry>>();
+
+    NaptrRecord { domain, entries, additional_srvs: vec![] }
+}
+
+pub(crate) fn srv_record_from(domain: SrvDomain, lookup: SrvLookup) -> SrvRecord {
+    let additional_addrs = addr_records_from(lookup.as_lookup().record_iter());
+    let entries = lookup.into_iter().map(Into::into).collect::<Vec<SrvEntry>>();
+
+    SrvRecord { domain, entries, additional_addrs }
+}
+
+pub(crate) fn cname_from(lookup: Lookup) -> Option<Domain> {
+    lookup.into_iter().find_map(|rdata| match rdata {
+        RData::CNAME(name) => Some(name.to_string().into()),
+        _ => None,
+    })
+}
+
+pub(crate) fn addr_record_from(domain: Domain, lookup: LookupIp) -> AddrRecord {
+    let ip_addrs = lookup.into_iter().collect::<Vec<IpAddr>>();
+
+    AddrRecord { domain, ip_addrs }
+}
+
+//groups any A/AAAA records (usually found in the additional section) by name
+pub(crate) fn addr_records_from<'a>(records: impl Iterator<Item = &'a Record>) -> Vec<AddrRecord> {
+    let mut addr_records: Vec<AddrRecord> = vec![];
+
+    for record in records {
+        let ip_addr: IpAddr = match record.rdata() {
+            RData::A(ip_addr) => (*ip_addr).into(),
+            RData::AAAA(ip_addr) => (*ip_addr).into(),
+            _ => continue,
+        };
+        let domain = record.name().to_string().into();
+
+        match addr_records.iter_mut().find(|addr_record| addr_record.domain == domain) {
+            Some(addr_record) => addr_record.ip_addrs.push(ip_addr),
+            None => addr_records.push(AddrRecord { domain, ip_addrs: vec![ip_addr] }),
+        }
+    }
+
+    addr_records
+}
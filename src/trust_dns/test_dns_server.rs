@@ -0,0 +1,343 @@
+use std::{
+    io::{self, Read, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::records::*;
+use trust_dns_proto::{
+    op::{Message, MessageType, ResponseCode},
+    rr::{
+        rdata::{naptr::NAPTR, srv::SRV},
+        record_data::RData,
+        record_type::RecordType,
+        resource::Record,
+        Name,
+    },
+};
+use trust_dns_resolver::config::{NameServerConfigGroup, ResolverConfig};
+
+use rsip::Domain;
+
+const TTL: u32 = 300;
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// In-memory zone served by a [TestDnsServer].
+///
+/// By default, like most authoritative servers, the server fills the additional section of the
+/// answers: SRV answers come along with the A/AAAA records of their targets and NAPTR answers
+/// (with `S` flag) come along with the SRV records of their replacements, if those exist in the
+/// zone. Use [TestZone::without_additionals] to disable that.
+#[derive(Debug, Clone)]
+pub struct TestZone {
+    records: Vec<Record>,
+    response_codes: Vec<(Name, ResponseCode)>,
+    additionals: bool,
+}
+
+/// A small authoritative DNS server that listens on a localhost UDP and TCP port (the same
+/// port for both) and answers from a [TestZone]. It is meant for integration tests, where a
+/// `trust-dns` resolver can be pointed to it using [TestDnsServer::resolver_config], so that full
+/// RFC 3263 lookups run over real DNS wire format, without leaving the host.
+///
+/// The server runs on its own threads, hence it works regardless of the async runtime used, and
+/// it stops once dropped.
+#[derive(Debug)]
+pub struct TestDnsServer {
+    addr: SocketAddr,
+    running: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Default for TestZone {
+    fn default() -> Self {
+        Self { records: vec![], response_codes: vec![], additionals: true }
+    }
+}
+
+impl TestZone {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_naptr(mut self, record: NaptrRecord) -> Self {
+        let name = name_from(&record.domain);
+        self.records.extend(record.entries.into_iter().map(|entry| {
+            let naptr = NAPTR::new(
+                entry.order,
+                entry.preference,
+                entry.flags.as_bytes().into(),
+                entry.services.to_string().into_bytes().into(),
+                entry.regexp.into(),
+                name_from(&entry.replacement),
+            );
+            Record::from_rdata(name.clone(), TTL, RData::NAPTR(naptr))
+        }));
+        self
+    }
+
+    pub fn with_srv(mut self, record: SrvRecord) -> Self {
+        let name = name_from(&record.domain.to_string().into());
+        self.records.extend(record.entries.into_iter().map(|entry| {
+            let srv =
+                SRV::new(entry.priority, entry.weight, entry.port.into(), name_from(&entry.target));
+            Record::from_rdata(name.clone(), TTL, RData::SRV(srv))
+        }));
+        self
+    }
+
+    pub fn with_addr(mut self, record: AddrRecord) -> Self {
+        let name = name_from(&record.domain);
+        self.records.extend(record.ip_addrs.into_iter().map(|ip_addr| {
+            let rdata = match ip_addr {
+                IpAddr::V4(ip_addr) => RData::A(ip_addr),
+                IpAddr::V6(ip_addr) => RData::AAAA(ip_addr),
+            };
+            Record::from_rdata(name.clone(), TTL, rdata)
+        }));
+        self
+    }
+
+    /// Adds a raw `trust-dns` record, useful for records that can't be expressed through the
+    /// records of this crate (like malformed ones).
+    pub fn with_record(mut self, record: Record) -> Self {
+        self.records.push(record);
+        self
+    }
+
+    /// Answers any query for the given domain with the given response code (like `SERVFAIL`)
+    /// and no records.
+    pub fn with_response_code(mut self, domain: Domain, response_code: ResponseCode) -> Self {
+        self.response_codes.push((name_from(&domain), response_code));
+        self
+    }
+
+    pub fn without_additionals(mut self) -> Self {
+        self.additionals = false;
+        self
+    }
+
+    fn records_of(&self, name: &Name, record_type: RecordType) -> Vec<Record> {
+        self.records
+            .iter()
+            .filter(|record| record.name() == name && record.record_type() == record_type)
+            .cloned()
+            .collect()
+    }
+
+    fn addr_records_of(&self, name: &Name) -> Vec<Record> {
+        let mut records = self.records_of(name, RecordType::A);
+        records.extend(self.records_of(name, RecordType::AAAA));
+        records
+    }
+
+    fn additionals_of(&self, answers: &[Record]) -> Vec<Record> {
+        let mut additionals: Vec<Record> = vec![];
+
+        for answer in answers {
+            let records = match answer.rdata() {
+                RData::SRV(srv) => self.addr_records_of(srv.target()),
+                RData::NAPTR(naptr) if naptr.flags().eq_ignore_ascii_case(b"S") => {
+                    let srvs = self.records_of(naptr.replacement(), RecordType::SRV);
+                    let mut records = self.additionals_of(&srvs);
+                    records.splice(0..0, srvs);
+                    records
+                }
+                _ => vec![],
+            };
+
+            records.into_iter().for_each(|record| {
+                if !additionals.contains(&record) {
+                    additionals.push(record)
+                }
+            });
+        }
+
+        additionals
+    }
+
+    fn answer(&self, request: &Message) -> Message {
+        let mut response = Message::new();
+        response
+            .set_id(request.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(request.op_code())
+            .set_authoritative(true)
+            .set_recursion_desired(request.recursion_desired())
+            .add_queries(request.queries().to_vec());
+
+        for query in request.queries() {
+            if let Some((_, response_code)) =
+                self.response_codes.iter().find(|(name, _)| name == query.name())
+            {
+                response.set_response_code(*response_code);
+                continue;
+            }
+
+            if !self.records.iter().any(|record| record.name() == query.name()) {
+                response.set_response_code(ResponseCode::NXDomain);
+                continue;
+            }
+
+            let answers = self.records_of(query.name(), query.query_type());
+            if self.additionals {
+                self.additionals_of(&answers).into_iter().for_each(|record| {
+                    response.add_additional(record);
+                });
+            }
+            response.add_answers(answers);
+        }
+
+        response
+    }
+}
+
+impl TestDnsServer {
+    /// Starts serving the zone on a random localhost port.
+    pub fn start(zone: TestZone) -> io::Result<Self> {
+        let (udp_socket, tcp_listener) = bind_localhost()?;
+        let addr = udp_socket.local_addr()?;
+        let running = Arc::new(AtomicBool::new(true));
+
+        udp_socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        tcp_listener.set_nonblocking(true)?;
+
+        let handles = vec![
+            {
+                let (zone, running) = (zone.clone(), running.clone());
+                thread::spawn(move || serve_udp(udp_socket, zone, running))
+            },
+            {
+                let running = running.clone();
+                thread::spawn(move || serve_tcp(tcp_listener, zone, running))
+            },
+        ];
+
+        Ok(Self { addr, running, handles })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// A `trust-dns` [ResolverConfig] that has this server (over UDP and TCP) as its only
+    /// name server.
+    pub fn resolver_config(&self) -> ResolverConfig {
+        ResolverConfig::from_parts(
+            None,
+            vec![],
+            NameServerConfigGroup::from_ips_clear(&[self.addr.ip()], self.addr.port(), true),
+        )
+    }
+}
+
+impl Drop for TestDnsServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.handles.drain(..).for_each(|handle| {
+            let _ = handle.join();
+        });
+    }
+}
+
+fn name_from(domain: &Domain) -> Name {
+    let mut name = Name::from_ascii(domain.to_string()).unwrap_or_else(|_| Name::root());
+    name.set_fqdn(true);
+    name
+}
+
+//UDP and TCP should share the same port, retry a few times in case the port that was picked for
+//UDP is taken for TCP
+fn bind_localhost() -> io::Result<(UdpSocket, TcpListener)> {
+    let mut last_error = None;
+
+    for _ in 0..10 {
+        let udp_socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+        match TcpListener::bind(udp_socket.local_addr()?) {
+            Ok(tcp_listener) => return Ok((udp_socket, tcp_listener)),
+            Err(error) => last_error = Some(error),
+        }
+    }
+
+    Err(last_error.expect("at least one bind attempt"))
+}
+
+//over UDP, answers that don't fit in the payload size of the request are truncated, so that the
+//resolver retries over TCP
+fn response_bytes(zone: &TestZone, request: &[u8], udp: bool) -> Option<Vec<u8>> {
+    let request = Message::from_vec(request).ok()?;
+    let response = zone.answer(&request);
+    let bytes = response.to_vec().ok()?;
+
+    match udp {
+        true if bytes.len() > request.max_payload() as usize => {
+            let mut truncated = response.truncate();
+            truncated.set_truncated(true);
+            truncated.to_vec().ok()
+        }
+        _ => Some(bytes),
+    }
+}
+
+fn serve_udp(socket: UdpSocket, zone: TestZone, running: Arc<AtomicBool>) {
+    let mut buf = [0u8; 4096];
+
+    while running.load(Ordering::SeqCst) {
+        let (len, peer) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(_) => continue,
+        };
+
+        if let Some(bytes) = response_bytes(&zone, &buf[..len], true) {
+            let _ = socket.send_to(&bytes, peer);
+        }
+    }
+}
+
+fn serve_tcp(listener: TcpListener, zone: TestZone, running: Arc<AtomicBool>) {
+    while running.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let _ = serve_tcp_stream(stream, &zone, &running);
+            }
+            Err(_) => thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+fn serve_tcp_stream(
+    mut stream: TcpStream,
+    zone: &TestZone,
+    running: &AtomicBool,
+) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+
+    while running.load(Ordering::SeqCst) {
+        let mut len = [0u8; 2];
+        match stream.read(&mut len[..1]) {
+            Ok(0) => return Ok(()),
+            Ok(_) => stream.read_exact(&mut len[1..])?,
+            Err(error) if is_timeout(&error) => continue,
+            Err(error) => return Err(error),
+        }
+        let mut request = vec![0u8; u16::from_be_bytes(len) as usize];
+        stream.read_exact(&mut request)?;
+
+        if let Some(bytes) = response_bytes(zone, &request, false) {
+            stream.write_all(&(bytes.len() as u16).to_be_bytes())?;
+            stream.write_all(&bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(error.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use std::{convert::TryInto, net::IpAddr, sync::Arc};
+use std::sync::Arc;
 
 use crate::{records::*, DnsClient, SrvDomain};
 use trust_dns_proto::rr::record_type::RecordType;
@@ -24,29 +24,41 @@ impl TrustDnsClient {
 #[async_trait]
 impl DnsClient for TrustDnsClient {
     async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
-        self.resolver.lookup(domain.to_string(), RecordType::NAPTR).ok().map(|r| {
-            let entries = r
-                .into_iter()
-                .filter_map(|rdata| rdata.try_into().ok())
-                .collect::<Vec<NaptrEntry>>();
-            NaptrRecord { domain, entries }
-        })
+        self.try_naptr_lookup(domain).await.ok().flatten()
     }
 
     async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
-        self.resolver.srv_lookup(domain.to_string()).ok().map(|r| {
-            let entries = r.into_iter().map(Into::into).collect::<Vec<SrvEntry>>();
-            SrvRecord { domain, entries }
-        })
+        self.try_srv_lookup(domain).await.ok().flatten()
     }
 
     async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
         self.resolver
             .lookup_ip(domain.to_string())
-            .map(|r| {
-                let ip_addrs = r.into_iter().map(Into::into).collect::<Vec<IpAddr>>();
-                AddrRecord { domain, ip_addrs }
-            })
+            .map(|r| super::addr_record_from(domain, r))
             .map_err(|e| Error::Unexpected(e.to_string()))
     }
+
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        let lookup = self.resolver.lookup(domain.to_string(), RecordType::NAPTR);
+
+        super::answer_from(lookup).map(|r| r.map(|r| super::naptr_record_from(domain, r)))
+    }
+
+    async fn try_srv_lookup(&self, domain: SrvDomain) -> Result<Option<SrvRecord>, Error> {
+        let lookup = self.resolver.srv_lookup(domain.to_string());
+
+        super::answer_from(lookup).map(|r| r.map(|r| super::srv_record_from(domain, r)))
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        let lookup = self.resolver.lookup_ip(domain.to_string());
+
+        super::answer_from(lookup).map(|r| r.map(|r| super::addr_record_from(domain, r)))
+    }
+
+    async fn try_cname_lookup(&self, domain: Domain) -> Result<Option<Domain>, Error> {
+        let lookup = self.resolver.lookup(domain.to_string(), RecordType::CNAME);
+
+        super::answer_from(lookup).map(|r| r.and_then(super::cname_from))
+    }
 }
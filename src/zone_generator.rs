@@ -0,0 +1,198 @@
+use crate::records::{NaptrEntry, NaptrFlags, NaptrRecord, NaptrServices, SrvEntry, SrvRecord};
+use crate::{SrvDomain, SrvService};
+use rsip::{Domain, Error, Port, Transport};
+use std::convert::TryFrom;
+
+/// Generates the NAPTR and SRV records that advertise a SIP deployment, from the nodes of the
+/// deployment and the (transport, port) pairs each node listens on, so that RFC 3263 clients find
+/// them. It's the inverse of a [Lookup](super::Lookup): the NAPTR services and SRV domains follow
+/// the same transport mapping ([NaptrServices::transport] and [SrvDomain]).
+///
+/// There is a NAPTR entry for every transport, in the order each transport is first found among
+/// the listeners of the nodes, pointing to the SRV domain of the transport. Each SRV record has an
+/// entry for every node that listens on that transport, with the priority and weight of the node.
+/// The A/AAAA records of the nodes are not generated.
+///
+/// ```
+/// use rsip::Transport;
+/// use rsip_dns::{Node, ZoneGenerator};
+///
+/// let zone = ZoneGenerator::new("example.com".into())
+///     .with_node(Node::new("sip1.example.com".into()).with_listener(Transport::Tls, 5061.into()))
+///     .with_node(
+///         Node::new("sip2.example.com".into())
+///             .with_listener(Transport::Tls, 5061.into())
+///             .with_priority(20),
+///     )
+///     .generate()
+///     .unwrap();
+///
+/// assert_eq!(
+///     zone.to_string(),
+///     r#"example.com. IN NAPTR 10 10 "S" "SIPS+D2T" "" _sips._tcp.example.com.
+/// _sips._tcp.example.com. IN SRV 10 10 5061 sip1.example.com.
+/// _sips._tcp.example.com. IN SRV 20 10 5061 sip2.example.com.
+/// "#
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ZoneGenerator {
+    domain: Domain,
+    nodes: Vec<Node>,
+    ttl: Option<u32>,
+}
+
+/// A node (server) of the deployment, given to the [ZoneGenerator]. The priority and weight are
+/// the ones of its SRV entries, 10 by default.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Node {
+    pub host: Domain,
+    pub listeners: Vec<(Transport, Port)>,
+    pub priority: u16,
+    pub weight: u16,
+}
+
+/// The records generated by [ZoneGenerator::generate]. Its `Display` renders them in zone file
+/// format, with absolute names, as [ZoneFileDnsClient](super::ZoneFileDnsClient) parses them.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GeneratedZone {
+    pub naptr_record: NaptrRecord,
+    pub srv_records: Vec<SrvRecord>,
+    pub ttl: Option<u32>,
+}
+
+impl ZoneGenerator {
+    pub fn new(domain: Domain) -> Self {
+        Self { domain, nodes: vec![], ttl: None }
+    }
+
+    pub fn with_node(mut self, node: Node) -> Self {
+        self.nodes.push(node);
+        self
+    }
+
+    /// Sets the TTL of the rendered records, otherwise they get the default TTL of the zone.
+    pub fn with_ttl(mut self, ttl: u32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Fails if a node listens on a transport that has no NAPTR service (like TLS-SCTP).
+    pub fn generate(&self) -> Result<GeneratedZone, Error> {
+        let mut transports: Vec<Transport> = vec![];
+        for (transport, _) in self.nodes.iter().flat_map(|node| node.listeners.iter()) {
+            if !transports.contains(transport) {
+                transports.push(*transport);
+            }
+        }
+
+        let mut naptr_entries = vec![];
+        let mut srv_records = vec![];
+        for (index, transport) in transports.into_iter().enumerate() {
+            let srv_domain = SrvDomain::from((SrvService::Sip, self.domain.clone(), transport));
+
+            naptr_entries.push(NaptrEntry {
+                order: 10 * (index as u16 + 1),
+                preference: 10,
+                flags: NaptrFlags::S,
+                services: NaptrServices::try_from(transport)?,
+                regexp: vec![],
+                replacement: srv_domain.to_string().into(),
+            });
+            srv_records.push(SrvRecord {
+                entries: self.srv_entries_for(transport),
+                domain: srv_domain,
+                additional_addrs: vec![],
+            });
+        }
+
+        Ok(GeneratedZone {
+            naptr_record: NaptrRecord {
+                entries: naptr_entries,
+                domain: self.domain.clone(),
+                additional_srvs: vec![],
+            },
+            srv_records,
+            ttl: self.ttl,
+        })
+    }
+
+    fn srv_entries_for(&self, transport: Transport) -> Vec<SrvEntry> {
+        self.nodes
+            .iter()
+            .flat_map(|node| {
+                node.listeners.iter().filter(|(other, _)| *other == transport).map(
+                    move |(_, port)| SrvEntry {
+                        priority: node.priority,
+                        weight: node.weight,
+                        port: *port,
+                        target: node.host.clone(),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+impl Node {
+    pub fn new(host: Domain) -> Self {
+        Self { host, listeners: vec![], priority: 10, weight: 10 }
+    }
+
+    pub fn with_listener(mut self, transport: Transport, port: Port) -> Self {
+        self.listeners.push((transport, port));
+        self
+    }
+
+    pub fn with_priority(mut self, priority: u16) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_weight(mut self, weight: u16) -> Self {
+        self.weight = weight;
+        self
+    }
+}
+
+impl std::fmt::Display for GeneratedZone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ttl = self.ttl.map(|ttl| format!(" {}", ttl)).unwrap_or_default();
+
+        for entry in self.naptr_record.entries.iter() {
+            writeln!(
+                f,
+                "{}{} IN NAPTR {} {} \"{}\" \"{}\" \"{}\" {}",
+                absolute(&self.naptr_record.domain),
+                ttl,
+                entry.order,
+                entry.preference,
+                String::from_utf8_lossy(entry.flags.as_bytes()),
+                entry.services,
+                String::from_utf8_lossy(&entry.regexp),
+                absolute(&entry.replacement)
+            )?;
+        }
+        for srv_record in self.srv_records.iter() {
+            for entry in srv_record.entries.iter() {
+                writeln!(
+                    f,
+                    "{}{} IN SRV {} {} {} {}",
+                    absolute(&srv_record.domain.to_string().into()),
+                    ttl,
+                    entry.priority,
+                    entry.weight,
+                    entry.port,
+                    absolute(&entry.target)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//zone file names without a trailing dot are relative to the origin
+fn absolute(domain: &Domain) -> String {
+    format!("{}.", domain.to_string().trim_end_matches('.'))
+}
@@ -0,0 +1,78 @@
+use rsip::Domain;
+use rsip_dns::{records::*, *};
+use std::net::IpAddr;
+use testing_utils::Randomize;
+
+#[tokio::test]
+async fn static_client_answers_configured_records() {
+    let srv_record = SrvRecord::random();
+    let dns_client = StaticDnsClient::new()
+        .with_srv(srv_record.clone())
+        .with_addr(AddrRecord {
+            domain: "sip.example.com".into(),
+            ip_addrs: vec!["10.0.0.1".parse().unwrap()],
+        })
+        .with_addr(AddrRecord {
+            domain: "SIP.example.com.".into(),
+            ip_addrs: vec!["10.0.0.2".parse().unwrap()],
+        });
+
+    assert_eq!(dns_client.srv_lookup(srv_record.domain.clone()).await, Some(srv_record));
+    assert_eq!(
+        dns_client.ip_lookup("sip.example.com".into()).await.unwrap().ip_addrs,
+        vec!["10.0.0.1".parse::<IpAddr>().unwrap(), "10.0.0.2".parse().unwrap()]
+    );
+    assert!(dns_client.naptr_lookup("example.com".into()).await.is_none());
+    assert!(dns_client.ip_lookup("example.com".into()).await.is_err());
+    assert!(matches!(dns_client.try_ip_lookup("example.com".into()).await, Ok(None)));
+}
+
+#[tokio::test]
+#[should_panic(expected = "unexpected Naptr query for example.com")]
+async fn static_client_panics_on_given_query_types() {
+    let dns_client = StaticDnsClient::new().panic_on(QueryType::Naptr);
+
+    assert!(dns_client.srv_lookup(SrvDomain::random()).await.is_none());
+    dns_client.naptr_lookup("example.com".into()).await;
+}
+
+#[tokio::test]
+async fn spy_client_records_calls() {
+    let dns_client = SpyDnsClient::new(StaticDnsClient::new())
+        .expect(QueryType::Naptr, 1)
+        .expect(QueryType::Addr, 2)
+        .expect(QueryType::Srv, 0);
+
+    dns_client.naptr_lookup("example.com".into()).await;
+    dns_client.clone().ip_lookup("sip1.example.com".into()).await.unwrap_err();
+    dns_client.try_ip_lookup("sip2.example.com".into()).await.unwrap();
+
+    assert_eq!(
+        dns_client.calls(),
+        vec![
+            (QueryType::Naptr, "example.com".into()),
+            (QueryType::Addr, "sip1.example.com".into()),
+            (QueryType::Addr, "sip2.example.com".into()),
+        ]
+    );
+    assert_eq!(
+        dns_client.calls_of(QueryType::Addr),
+        vec![String::from("sip1.example.com"), "sip2.example.com".into()]
+    );
+    dns_client.verify();
+}
+
+#[tokio::test]
+#[should_panic(expected = "expected 1 Srv queries, got 0")]
+async fn spy_client_verifies_expectations() {
+    let dns_client = SpyDnsClient::new(StaticDnsClient::new()).expect(QueryType::Srv, 1);
+
+    dns_client.naptr_lookup(Domain::from("example.com")).await;
+    dns_client.verify();
+}
+
+#[tokio::test]
+#[should_panic(expected = "unexpected A/AAAA query for example.com")]
+async fn panic_client_panics() {
+    let _ = PanicDnsClient.ip_lookup("example.com".into()).await;
+}
@@ -0,0 +1,138 @@
+use rsip::{Domain, Error};
+use rsip_dns::{records::*, *};
+use std::{
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[derive(Debug, Clone)]
+enum Behavior {
+    Fail,
+    Hang,
+    Answer(ZoneFileDnsClient),
+}
+
+#[derive(Debug, Clone)]
+struct StubDnsClient {
+    behavior: Behavior,
+    calls: Arc<AtomicUsize>,
+}
+
+impl StubDnsClient {
+    fn new(behavior: Behavior) -> Self {
+        Self { behavior, calls: Default::default() }
+    }
+
+    fn answering(zone: &str) -> Self {
+        Self::new(Behavior::Answer(ZoneFileDnsClient::parse(zone).unwrap()))
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait::async_trait]
+impl DnsClient for StubDnsClient {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        self.try_naptr_lookup(domain).await.ok().flatten()
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        self.try_srv_lookup(domain).await.ok().flatten()
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        self.try_ip_lookup(domain).await?.ok_or_else(|| Error::Unexpected("no records".into()))
+    }
+
+    async fn try_naptr_lookup(&self, domain: Domain) -> Result<Option<NaptrRecord>, Error> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        match &self.behavior {
+            Behavior::Fail => Err(Error::Unexpected("SERVFAIL".into())),
+            Behavior::Hang => futures::future::pending().await,
+            Behavior::Answer(client) => client.try_naptr_lookup(domain).await,
+        }
+    }
+
+    async fn try_ip_lookup(&self, domain: Domain) -> Result<Option<AddrRecord>, Error> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        match &self.behavior {
+            Behavior::Fail => Err(Error::Unexpected("SERVFAIL".into())),
+            Behavior::Hang => futures::future::pending().await,
+            Behavior::Answer(client) => client.try_ip_lookup(domain).await,
+        }
+    }
+}
+
+async fn ip_of(dns_client: &impl DnsClient, domain: &str) -> Option<IpAddr> {
+    dns_client.ip_lookup(domain.into()).await.ok().map(|record| record.ip_addrs[0])
+}
+
+#[tokio::test]
+async fn falls_back_on_errors_and_skips_unhealthy_clients() {
+    let primary = StubDnsClient::new(Behavior::Fail);
+    let secondary = StubDnsClient::answering("example.com. A 10.0.0.2");
+    let dns_client = FallbackDnsClient::new(vec![primary.clone(), secondary.clone()]);
+
+    assert_eq!(ip_of(&dns_client, "example.com").await, Some("10.0.0.2".parse().unwrap()));
+    assert_eq!(dns_client.health(), vec![false, true]);
+
+    assert_eq!(ip_of(&dns_client, "example.com").await, Some("10.0.0.2".parse().unwrap()));
+    assert_eq!((primary.calls(), secondary.calls()), (1, 2));
+}
+
+#[tokio::test]
+async fn does_not_fall_back_on_empty_answers() {
+    let primary = StubDnsClient::answering("example.com. A 10.0.0.1");
+    let secondary = StubDnsClient::answering("example.org. A 10.0.0.2");
+    let dns_client = FallbackDnsClient::new(vec![primary.clone(), secondary.clone()]);
+
+    assert_eq!(dns_client.naptr_lookup("example.org".into()).await, None);
+    assert_eq!(ip_of(&dns_client, "example.org").await, None);
+    assert_eq!((primary.calls(), secondary.calls()), (2, 0));
+    assert_eq!(dns_client.health(), vec![true, true]);
+}
+
+#[tokio::test]
+async fn falls_back_on_timeouts() {
+    let primary = StubDnsClient::new(Behavior::Hang);
+    let secondary = StubDnsClient::answering("example.com. A 10.0.0.2");
+    let dns_client =
+        FallbackDnsClient::new(vec![primary, secondary]).with_timeout(Duration::from_millis(50));
+
+    assert_eq!(ip_of(&dns_client, "example.com").await, Some("10.0.0.2".parse().unwrap()));
+    assert_eq!(dns_client.health(), vec![false, true]);
+}
+
+#[tokio::test]
+async fn retries_unhealthy_clients_after_cooldown() {
+    let primary = StubDnsClient::new(Behavior::Fail);
+    let secondary = StubDnsClient::answering("example.com. A 10.0.0.2");
+    let dns_client = FallbackDnsClient::new(vec![primary.clone(), secondary])
+        .with_cooldown(Duration::from_millis(50));
+
+    ip_of(&dns_client, "example.com").await;
+    assert_eq!(dns_client.health(), vec![false, true]);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(dns_client.health(), vec![true, true]);
+
+    ip_of(&dns_client, "example.com").await;
+    assert_eq!(primary.calls(), 2);
+}
+
+#[tokio::test]
+async fn tries_all_clients_when_all_are_unhealthy() {
+    let primary = StubDnsClient::new(Behavior::Fail);
+    let secondary = StubDnsClient::new(Behavior::Fail);
+    let dns_client = FallbackDnsClient::new(vec![primary.clone(), secondary.clone()]);
+
+    assert!(dns_client.try_ip_lookup("example.com".into()).await.is_err());
+    assert!(dns_client.try_ip_lookup("example.com".into()).await.is_err());
+    assert_eq!((primary.calls(), secondary.calls()), (2, 2));
+}
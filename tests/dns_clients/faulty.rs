@@ -0,0 +1,103 @@
+use rsip_dns::*;
+use std::{
+    convert::TryFrom,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+const ZONE: &str = r#"
+$ORIGIN example.com.
+_sip._udp  SRV 10 10 5060 sip1
+           SRV 20 10 5060 sip2
+           SRV 30 10 5060 sip3
+sip1       A 10.0.0.1
+sip1       A 10.0.0.2
+sip1       A 10.0.0.3
+sip1       A 10.0.0.4
+sip1       A 10.0.0.5
+"#;
+
+fn zone() -> ZoneFileDnsClient {
+    ZoneFileDnsClient::parse(ZONE).unwrap()
+}
+
+fn srv_domain() -> SrvDomain {
+    SrvDomain::try_from("_sip._udp.example.com").unwrap()
+}
+
+#[tokio::test]
+async fn injects_scripted_faults() {
+    use Fault::*;
+
+    let dns_client = FaultyDnsClient::new(zone(), 1).with_script(
+        QueryType::Srv,
+        vec![vec![ServFail], vec![TruncatedSrv(1)], vec![Empty], vec![]],
+    );
+
+    assert!(dns_client.try_srv_lookup(srv_domain()).await.is_err());
+    assert_eq!(dns_client.srv_lookup(srv_domain()).await.unwrap().entries.len(), 1);
+    assert!(matches!(dns_client.try_srv_lookup(srv_domain()).await, Ok(None)));
+    assert_eq!(dns_client.srv_lookup(srv_domain()).await.unwrap().entries.len(), 3);
+    //the script only affects SRV queries
+    assert!(dns_client.ip_lookup("sip1.example.com".into()).await.is_ok());
+}
+
+#[tokio::test]
+async fn injects_latency_and_timeouts() {
+    let dns_client = FaultyDnsClient::new(zone(), 1).with_script(
+        QueryType::Addr,
+        vec![
+            vec![Fault::Latency(Duration::from_millis(50))],
+            vec![Fault::Timeout(Duration::from_millis(50))],
+        ],
+    );
+
+    let now = Instant::now();
+    assert!(dns_client.ip_lookup("sip1.example.com".into()).await.is_ok());
+    assert!(dns_client.ip_lookup("sip1.example.com".into()).await.is_err());
+    assert!(now.elapsed() >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn same_seed_reproduces_the_same_faults() {
+    async fn run(seed: u64) -> Vec<Option<Vec<IpAddr>>> {
+        let dns_client = FaultyDnsClient::new(zone(), seed)
+            .with_fault(QueryType::Addr, 0.3, Fault::ServFail)
+            .with_fault(QueryType::Addr, 0.5, Fault::Shuffled);
+
+        let mut answers = vec![];
+        for _ in 0..20 {
+            let answer = dns_client.ip_lookup("sip1.example.com".into()).await;
+            answers.push(answer.ok().map(|record| record.ip_addrs));
+        }
+
+        answers
+    }
+
+    let answers = run(42).await;
+    assert_eq!(answers, run(42).await);
+    assert_ne!(answers, run(43).await);
+    assert!(answers.iter().any(Option::is_none));
+    assert!(answers.iter().any(Option::is_some));
+}
+
+#[test]
+#[should_panic(expected = "can't be NaN")]
+fn rejects_nan_probabilities() {
+    FaultyDnsClient::new(zone(), 42).with_fault(QueryType::Addr, f64::NAN, Fault::ServFail);
+}
+
+#[tokio::test]
+async fn drives_fallback_on_timeouts() {
+    let primary = FaultyDnsClient::new(zone(), 1).with_fault(
+        QueryType::Addr,
+        1.0,
+        Fault::Timeout(Duration::from_secs(10)),
+    );
+    let secondary = FaultyDnsClient::new(zone(), 1);
+    let dns_client =
+        FallbackDnsClient::new(vec![primary, secondary]).with_timeout(Duration::from_millis(50));
+
+    assert!(dns_client.ip_lookup("sip1.example.com".into()).await.is_ok());
+    assert_eq!(dns_client.health(), vec![false, true]);
+}
@@ -0,0 +1,8 @@
+pub mod fakes;
+pub mod fallback;
+pub mod faulty;
+pub mod overrides;
+#[cfg(feature = "serde")]
+pub mod record_replay;
+pub mod split_horizon;
+pub mod zone_file;
@@ -0,0 +1,66 @@
+use rsip::Domain;
+use rsip_dns::{records::*, *};
+use std::convert::TryFrom;
+use std::net::IpAddr;
+
+#[tokio::test]
+async fn answers_overrides_without_touching_inner_client() {
+    let srv_domain = SrvDomain::try_from("_sip._udp.carrier.example.net").unwrap();
+    let dns_client = OverrideDnsClient::new(PanicDnsClient)
+        .with_naptr(NaptrRecord {
+            domain: "carrier.example.net".into(),
+            entries: vec![],
+            additional_srvs: vec![],
+        })
+        .with_srv(SrvRecord {
+            domain: srv_domain.clone(),
+            entries: vec![SrvEntry {
+                priority: 10,
+                weight: 10,
+                port: 5060.into(),
+                target: "sbc.lab".into(),
+            }],
+            additional_addrs: vec![],
+        })
+        .with_hosts("# lab\n10.0.0.1 sbc.lab sbc\n\n::1 sbc.lab # ipv6 too\n")
+        .unwrap();
+
+    assert!(dns_client.naptr_lookup("Carrier.Example.net.".into()).await.is_some());
+    assert_eq!(
+        dns_client.srv_lookup(srv_domain).await.unwrap().targets(),
+        vec![Domain::from("sbc.lab")]
+    );
+    assert_eq!(
+        dns_client.ip_lookup("sbc.lab".into()).await.unwrap().ip_addrs,
+        vec!["10.0.0.1".parse::<IpAddr>().unwrap(), "::1".parse().unwrap()]
+    );
+    assert!(dns_client.ip_lookup("SBC".into()).await.is_ok());
+}
+
+#[tokio::test]
+async fn falls_through_to_inner_client() {
+    let inner = ZoneFileDnsClient::parse("www.example.com. A 10.0.0.2").unwrap();
+    let dns_client = OverrideDnsClient::new(inner).with_hosts("10.0.0.1 sbc.lab").unwrap();
+
+    assert_eq!(
+        dns_client.ip_lookup("www.example.com".into()).await.unwrap().ip_addrs,
+        vec!["10.0.0.2".parse::<IpAddr>().unwrap()]
+    );
+    assert!(dns_client.ip_lookup("example.com".into()).await.is_err());
+    assert!(dns_client.naptr_lookup("sbc.lab".into()).await.is_none());
+}
+
+#[test]
+fn reports_hosts_errors_with_line_numbers() {
+    let errors = vec![
+        ("10.0.0.1 sbc\n10.0.0.300 other", "line 2: invalid ip"),
+        ("\n\n::1", "line 3: missing"),
+    ];
+
+    for (hosts, message) in errors {
+        match OverrideDnsClient::new(PanicDnsClient).with_hosts(hosts) {
+            Err(rsip::Error::ParseError(error)) => assert!(error.starts_with(message), "{}", error),
+            other => panic!("expected parse error for {:?}, got {:?}", hosts, other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use rsip::Transport;
+use rsip_dns::{records::*, *};
+use std::convert::TryFrom;
+
+const ZONE: &str = r#"
+$ORIGIN example.com.
+@          NAPTR 10 10 "S" "SIP+D2T" "" _sip._tcp
+_sip._tcp  SRV   10 10 5060 sip1
+_sip._udp  SRV   10 10 5060 sip2
+sip1       A     10.0.0.1
+sip1       A     10.0.0.2
+example.com. A   10.0.0.3
+www          CNAME sip1
+"#;
+
+async fn targets_of<C: DnsClient + 'static>(dns_client: C) -> Vec<(String, Transport)> {
+    let mut lookup = Lookup::from(Context::new("example.com".into(), dns_client));
+
+    let mut targets = vec![];
+    while let Some(target) = lookup.resolve_next().await {
+        targets.push((target.socket_addr().to_string(), target.transport));
+    }
+
+    targets
+}
+
+#[tokio::test]
+async fn replays_recorded_lookup() {
+    let path =
+        std::env::temp_dir().join(format!("rsip-dns-recording-{}.jsonl", std::process::id()));
+    let dns_client =
+        RecordingDnsClient::with_file(ZoneFileDnsClient::parse(ZONE).unwrap(), &path).unwrap();
+
+    let recorded_targets = targets_of(dns_client.clone()).await;
+    assert!(!recorded_targets.is_empty());
+
+    let recording = Recording::from_path(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(recording, dns_client.recording());
+    //sip2 has no addresses, hence the empty answer
+    assert!(recording.answers().iter().any(|answer| matches!(
+        answer,
+        RecordedAnswer::Addr { domain, answer: Ok(None) } if domain == &"sip2.example.com".into()
+    )));
+
+    assert_eq!(targets_of(ReplayDnsClient::new(recording)).await, recorded_targets);
+}
+
+#[test]
+fn recording_round_trips() {
+    let srv_domain = SrvDomain::try_from("_sips._tcp.example.com").unwrap();
+    let recording = Recording::from(vec![
+        RecordedAnswer::Naptr {
+            domain: "example.com".into(),
+            answer: Ok(Some(NaptrRecord {
+                domain: "example.com".into(),
+                entries: vec![NaptrEntry {
+                    order: 10,
+                    preference: 20,
+                    flags: NaptrFlags::S,
+                    services: NaptrServices::SipsD2t,
+                    regexp: br#"!^.*$!sip:"quoted"\@example.com!"#.to_vec(),
+                    replacement: "_sips._tcp.example.com".into(),
+                }],
+                additional_srvs: vec![SrvRecord {
+                    domain: srv_domain.clone(),
+                    entries: vec![SrvEntry {
+                        priority: 1,
+                        weight: 2,
+                        port: 5061.into(),
+                        target: "sip1.example.com".into(),
+                    }],
+                    additional_addrs: vec![AddrRecord {
+                        domain: "sip1.example.com".into(),
+                        ip_addrs: vec!["10.0.0.1".parse().unwrap(), "::1".parse().unwrap()],
+                    }],
+                }],
+            })),
+        },
+        RecordedAnswer::Srv { domain: srv_domain, answer: Ok(None) },
+        RecordedAnswer::Addr {
+            domain: "sip1.example.com".into(),
+            answer: Err("request timed out".into()),
+        },
+        RecordedAnswer::Addr {
+            domain: "sip1.example.com".into(),
+            answer: Ok(Some(AddrRecord {
+                domain: "sip1.example.com".into(),
+                ip_addrs: vec!["10.0.0.1".parse().unwrap()],
+            })),
+        },
+        RecordedAnswer::Cname {
+            domain: "www.example.com".into(),
+            answer: Ok(Some("sip1.example.com".into())),
+        },
+        RecordedAnswer::Cname { domain: "sip1.example.com".into(), answer: Ok(None) },
+        RecordedAnswer::Cname { domain: "sip2.example.com".into(), answer: Err("SERVFAIL".into()) },
+    ]);
+
+    assert_eq!(recording.to_string().parse::<Recording>().unwrap(), recording);
+}
+
+#[tokio::test]
+async fn records_and_replays_cname_lookups() {
+    let dns_client = RecordingDnsClient::new(ZoneFileDnsClient::parse(ZONE).unwrap());
+
+    let canonical = dns_client.try_cname_lookup("www.example.com".into()).await.unwrap();
+    assert_eq!(canonical, Some("sip1.example.com".into()));
+    assert_eq!(dns_client.try_cname_lookup("sip1.example.com".into()).await.unwrap(), None);
+
+    let recording = dns_client.recording();
+    assert_eq!(
+        recording.to_string(),
+        concat!(
+            r#"{"query":"cname","domain":"www.example.com","answer":{"Ok":"sip1.example.com"}}"#,
+            "\n",
+            r#"{"query":"cname","domain":"sip1.example.com","answer":{"Ok":null}}"#,
+            "\n"
+        )
+    );
+
+    let dns_client = ReplayDnsClient::new(recording);
+    assert_eq!(dns_client.try_cname_lookup("www.example.com".into()).await.unwrap(), canonical);
+    assert_eq!(dns_client.try_cname_lookup("sip1.example.com".into()).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn replays_answers_in_recorded_order() {
+    //a flapping resolver
+    let recording = concat!(
+        r#"{"query":"addr","domain":"sip1.example.com","answer":{"Err":"SERVFAIL"}}"#,
+        "\n",
+        r#"{"query":"addr","domain":"sip1.example.com","answer":{"Ok":{"domain":"sip1.example.com","ip_addrs":["10.0.0.1"]}}}"#,
+        "\n"
+    )
+    .parse::<Recording>()
+    .unwrap();
+    let dns_client = ReplayDnsClient::new(recording);
+
+    assert!(dns_client.try_ip_lookup("sip1.example.com".into()).await.is_err());
+    assert!(dns_client.ip_lookup("SIP1.example.com.".into()).await.is_ok());
+    assert!(dns_client.ip_lookup("sip1.example.com".into()).await.is_ok());
+}
+
+#[tokio::test]
+#[should_panic(expected = "query `naptr example.com` is not part of the recording")]
+async fn panics_on_unknown_queries() {
+    let dns_client = ReplayDnsClient::new(Recording::from(vec![RecordedAnswer::Addr {
+        domain: "sip1.example.com".into(),
+        answer: Ok(None),
+    }]));
+
+    dns_client.naptr_lookup("example.com".into()).await;
+}
+
+#[test]
+fn reports_parse_errors_with_line_numbers() {
+    let errors = vec![
+        (
+            "\n{\"query\":\"srv\",\"domain\":\"example.com\",\"answer\":{\"Ok\":null}}",
+            "line 2: invalid SRV domain",
+        ),
+        (
+            "{\"query\":\"txt\",\"domain\":\"example.com\",\"answer\":{\"Ok\":null}}",
+            "line 1: unknown",
+        ),
+        ("{\"query\":\"addr\",\"domain\":\"example.com\"", "line 1: EOF"),
+    ];
+
+    for (recording, message) in errors {
+        match recording.parse::<Recording>() {
+            Err(rsip::Error::ParseError(error)) => assert!(error.starts_with(message), "{}", error),
+            other => panic!("expected parse error for {:?}, got {:?}", recording, other),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use rsip_dns::{records::*, *};
+use std::convert::TryFrom;
+use std::net::IpAddr;
+
+fn dns_client() -> SplitHorizonDnsClient<ZoneFileDnsClient, ZoneFileDnsClient> {
+    let public = ZoneFileDnsClient::parse(
+        "corp.example. A 192.0.2.1\n\
+         sip.corp.example. A 192.0.2.2\n\
+         notcorp.example. A 192.0.2.3",
+    )
+    .unwrap();
+    let corp = ZoneFileDnsClient::parse(
+        "corp.example. A 10.0.0.1\n\
+         sip.corp.example. A 10.0.0.2\n\
+         _sip._tcp.corp.example. SRV 10 10 5060 sip.corp.example.",
+    )
+    .unwrap();
+    let lab = ZoneFileDnsClient::parse("sip.lab.corp.example. A 10.1.0.1").unwrap();
+
+    SplitHorizonDnsClient::new(public)
+        .with_route("corp.example.", corp)
+        .with_route("LAB.corp.example", lab)
+}
+
+async fn ip_of(dns_client: &impl DnsClient, domain: &str) -> Option<IpAddr> {
+    dns_client.ip_lookup(domain.into()).await.ok().map(|record| record.ip_addrs[0])
+}
+
+#[tokio::test]
+async fn routes_by_longest_suffix() {
+    let dns_client = dns_client();
+
+    assert_eq!(ip_of(&dns_client, "corp.example").await, Some("10.0.0.1".parse().unwrap()));
+    assert_eq!(ip_of(&dns_client, "SIP.corp.example.").await, Some("10.0.0.2".parse().unwrap()));
+    assert_eq!(ip_of(&dns_client, "sip.lab.corp.example").await, Some("10.1.0.1".parse().unwrap()));
+    assert_eq!(ip_of(&dns_client, "notcorp.example").await, Some("192.0.2.3".parse().unwrap()));
+    assert_eq!(ip_of(&dns_client, "www.lab.corp.example").await, None);
+}
+
+#[tokio::test]
+async fn routes_srv_domains_by_underlying_domain() {
+    let dns_client = dns_client();
+
+    let srv_record =
+        dns_client.srv_lookup(SrvDomain::try_from("_sip._tcp.corp.example").unwrap()).await;
+    assert_eq!(srv_record.map(|record| record.entries.len()), Some(1));
+    assert!(dns_client
+        .srv_lookup(SrvDomain::try_from("_sip._tcp.example.com").unwrap())
+        .await
+        .is_none());
+}
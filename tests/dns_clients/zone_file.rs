@@ -0,0 +1,127 @@
+use rsip::{Domain, Transport::*};
+use rsip_dns::{records::*, *};
+use std::convert::TryFrom;
+
+const ZONE: &str = r#"
+$ORIGIN example.com.
+$TTL 1h
+@         IN SOA ns1 hostmaster (
+              2021090101 ; serial
+              7200 3600 1209600 3600 )
+          IN NS  ns1
+@    3600 IN NAPTR 10 10 "S" "SIPS+D2T" "" _sips._tcp
+          IN NAPTR 20 10 "s" "SIP+D2U" "" _sip._udp.example.com.
+_sips._tcp     IN SRV 10 10 5061 sip1
+_sip._udp  300 IN SRV 10 10 5060 sip2.example.com.
+_sip._udp  IN 300 SRV 20 10 5060 sip1
+sip1           A     10.0.0.1
+sip1           AAAA  ::1
+SIP2           A     10.0.0.2 ; uppercase owners are fine too
+www            CNAME sip1
+"#;
+
+#[tokio::test]
+async fn resolves_from_zone_file() {
+    let dns_client = ZoneFileDnsClient::parse(ZONE).unwrap();
+
+    let naptr_record = dns_client.naptr_lookup("EXAMPLE.com.".into()).await.unwrap();
+    assert_eq!(
+        naptr_record.entries,
+        vec![
+            NaptrEntry {
+                order: 10,
+                preference: 10,
+                flags: NaptrFlags::S,
+                services: NaptrServices::SipsD2t,
+                regexp: vec![],
+                replacement: "_sips._tcp.example.com".into()
+            },
+            NaptrEntry {
+                order: 20,
+                preference: 10,
+                flags: NaptrFlags::Other(b"s".to_vec()),
+                services: NaptrServices::SipD2u,
+                regexp: vec![],
+                replacement: "_sip._udp.example.com".into()
+            },
+        ]
+    );
+
+    let srv_record =
+        dns_client.srv_lookup(SrvDomain::try_from("_sip._udp.example.com").unwrap()).await.unwrap();
+    assert_eq!(srv_record.domain.transport(), Udp);
+    assert_eq!(
+        srv_record.targets(),
+        vec![Domain::from("sip2.example.com"), Domain::from("sip1.example.com")]
+    );
+
+    let addr_record = dns_client.ip_lookup("sip1.example.com".into()).await.unwrap();
+    assert_eq!(
+        addr_record.ip_addrs,
+        vec!["10.0.0.1".parse::<std::net::IpAddr>().unwrap(), "::1".parse().unwrap()]
+    );
+    assert!(dns_client.ip_lookup("sip2.example.com".into()).await.is_ok());
+    assert!(dns_client.ip_lookup("sip3.example.com".into()).await.is_err());
+    assert!(dns_client.naptr_lookup("example.org".into()).await.is_none());
+
+    let canonical = dns_client.try_cname_lookup("WWW.example.com.".into()).await.unwrap();
+    assert_eq!(canonical, Some("sip1.example.com".into()));
+    assert_eq!(dns_client.try_cname_lookup("sip1.example.com".into()).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn context_lookup_from_zone_file() {
+    let context = Context::new("example.com".into(), ZoneFileDnsClient::parse(ZONE).unwrap())
+        .with_secure(true);
+
+    let target = Lookup::from(context).resolve_next().await.unwrap();
+    assert_eq!(target.socket_addr(), "10.0.0.1:5061".parse().unwrap());
+    assert_eq!(target.transport, Tls);
+}
+
+#[test]
+fn parses_with_initial_origin() {
+    let dns_client =
+        ZoneFileDnsClient::parse_with_origin("@ A 10.0.0.1\nsip1 A 10.0.0.2", "example.com".into())
+            .unwrap();
+
+    let mut domains =
+        dns_client.addr_records().into_iter().map(|record| record.domain).collect::<Vec<_>>();
+    domains.sort_by_key(|domain| domain.to_string());
+    assert_eq!(domains, vec![Domain::from("example.com"), Domain::from("sip1.example.com")]);
+}
+
+#[test]
+fn reports_parse_errors_with_line_numbers() {
+    let errors = vec![
+        ("sip1 A 10.0.0.1", 1, "relative name"),
+        ("$ORIGIN example.com.\n\n_sip._udp SRV 10 10 sip1", 3, "SRV record expects"),
+        ("$ORIGIN example.com.\nsip1 A 10.0.0.300", 2, "invalid IPv4"),
+        (
+            "$ORIGIN example.com.\n@ NAPTR ( 10 10 \"S\"\n \"SIP+D2U\" \"\" _sip._udp )\nwww CH A 1.1.1.1",
+            4,
+            "class",
+        ),
+        ("$TTL 1x", 1, "invalid TTL"),
+        ("$INCLUDE other.zone", 1, "$INCLUDE"),
+        ("$ORIGIN example.com.\n@ NAPTR ( 10 10", 2, "unbalanced"),
+        (
+            "$ORIGIN example.com.\nsip1 A 10.0.0.1\nsip1.example.com SRV 1 1 5060 sip1",
+            3,
+            "SRV owner",
+        ),
+        ("  A 10.0.0.1", 1, "without owner"),
+        ("$ORIGIN example.com.\nwww CNAME sip1 sip2", 2, "single name"),
+        ("$ORIGIN example.com.\nwww CNAME sip1\nwww CNAME sip2", 3, "more than one CNAME"),
+    ];
+
+    for (zone, line, message) in errors {
+        match ZoneFileDnsClient::parse(zone) {
+            Err(rsip::Error::ParseError(error)) => {
+                assert!(error.starts_with(&format!("line {}: ", line)), "{}", error);
+                assert!(error.contains(message), "{}", error);
+            }
+            other => panic!("expected parse error for {:?}, got {:?}", zone, other),
+        }
+    }
+}
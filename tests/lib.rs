@@ -1,4 +1,9 @@
+pub mod dns_clients;
+pub mod linter;
 pub mod lookups;
 pub mod records;
 pub mod resolvables;
 pub mod support;
+#[cfg(feature = "trust-dns")]
+pub mod trust_dns;
+pub mod zone_generator;
@@ -0,0 +1,246 @@
+use rsip::Transport;
+use rsip_dns::{records::*, *};
+use std::convert::TryFrom;
+
+#[tokio::test]
+async fn reports_nothing_for_a_sane_domain() {
+    let report = Linter::new(dns_client()).lint("example.com".into()).await;
+
+    assert_eq!(report.problems, vec![]);
+    assert!(!report.has_errors());
+}
+
+#[tokio::test]
+async fn reports_naptr_problems() {
+    let dns_client = dns_client().with_naptr(NaptrRecord {
+        domain: "example.com".into(),
+        entries: vec![
+            naptr_entry(NaptrServices::SipsD2t, "_sips._tcp.example.com"),
+            naptr_entry(NaptrServices::SipD2u, "_sip._udp.example.com"),
+            //the SRV record doesn't exist
+            naptr_entry(NaptrServices::SipD2t, "_sip._tcp.example.com"),
+            //not a SRV domain
+            naptr_entry(NaptrServices::SipD2s, "sctp.example.com"),
+            //points to a SRV domain of another transport
+            naptr_entry(NaptrServices::SipD2w, "_sip._udp.example.com"),
+            naptr_entry(NaptrServices::Other("E2U+sip".into()), "_sip._udp.example.com"),
+            NaptrEntry {
+                flags: NaptrFlags::A,
+                ..naptr_entry(NaptrServices::SipD2u, "udp.example.com")
+            },
+        ],
+        additional_srvs: vec![],
+    });
+
+    let problems = Linter::new(dns_client).lint("example.com".into()).await.problems;
+
+    assert_eq!(
+        problems,
+        vec![
+            Problem::MissingSrv {
+                entry: naptr_entry(NaptrServices::SipD2t, "_sip._tcp.example.com"),
+                replacement: SrvDomain::try_from("_sip._tcp.example.com").unwrap()
+            },
+            Problem::InvalidNaptrReplacement(naptr_entry(
+                NaptrServices::SipD2s,
+                "sctp.example.com"
+            )),
+            Problem::NaptrReplacementMismatch {
+                entry: naptr_entry(NaptrServices::SipD2w, "_sip._udp.example.com"),
+                replacement: SrvDomain::try_from("_sip._udp.example.com").unwrap()
+            },
+            Problem::UnsupportedNaptrService(naptr_entry(
+                NaptrServices::Other("E2U+sip".into()),
+                "_sip._udp.example.com"
+            )),
+            Problem::UnsupportedNaptrFlags(NaptrEntry {
+                flags: NaptrFlags::A,
+                ..naptr_entry(NaptrServices::SipD2u, "udp.example.com")
+            }),
+        ]
+    );
+    assert_eq!(problems[0].severity(), Severity::Error);
+    assert_eq!(problems[2].severity(), Severity::Warning);
+    assert_eq!(
+        problems[2].to_string(),
+        "NAPTR SIP+D2W points to _sip._udp.example.com, which is meant for UDP"
+    );
+}
+
+#[tokio::test]
+async fn reports_sips_without_tls_srv() {
+    let dns_client = StaticDnsClient::new().with_naptr(NaptrRecord {
+        domain: "example.com".into(),
+        entries: vec![naptr_entry(NaptrServices::SipsD2t, "_sips._tcp.example.com")],
+        additional_srvs: vec![],
+    });
+
+    let report = Linter::new(dns_client).lint("example.com".into()).await;
+
+    assert_eq!(
+        report.problems,
+        vec![Problem::SipsWithoutTlsSrv {
+            entry: naptr_entry(NaptrServices::SipsD2t, "_sips._tcp.example.com"),
+            replacement: SrvDomain::try_from("_sips._tcp.example.com").unwrap()
+        }]
+    );
+    assert!(report.has_errors());
+}
+
+#[tokio::test]
+async fn reports_missing_tls_srv_without_naptr() {
+    let dns_client = StaticDnsClient::new()
+        .with_srv(srv_record("_sip._udp.example.com", &["sip.example.com"]))
+        .with_addr(addr_record("sip.example.com"));
+
+    let report = Linter::new(dns_client.clone()).lint("example.com".into()).await;
+
+    let tls_srv = SrvDomain::try_from("_sips._tcp.example.com").unwrap();
+    assert_eq!(report.problems, vec![Problem::MissingTlsSrv(tls_srv)]);
+    assert_eq!(
+        report.problems[0].to_string(),
+        "_sips._tcp.example.com has no SRV records (and there is no NAPTR), SIPS clients fall back \
+         to A/AAAA"
+    );
+    assert!(!report.has_errors());
+
+    //not a problem when TLS is not supported
+    let linter = Linter::new(dns_client)
+        .with_supported_transports(SupportedTransports::only(vec![Transport::Udp]));
+    assert_eq!(linter.lint("example.com".into()).await.problems, vec![]);
+}
+
+#[tokio::test]
+async fn reports_unsupported_transports() {
+    let linter = Linter::new(dns_client())
+        .with_supported_transports(SupportedTransports::only(vec![Transport::Udp]));
+
+    let problems = linter.lint("example.com".into()).await.problems;
+
+    assert_eq!(
+        problems,
+        vec![Problem::UnsupportedNaptrService(naptr_entry(
+            NaptrServices::SipsD2t,
+            "_sips._tcp.example.com"
+        ))]
+    );
+}
+
+#[tokio::test]
+async fn reports_srv_target_problems() {
+    let dns_client = StaticDnsClient::new()
+        .with_srv(srv_record("_sip._udp.example.com", &["udp1.example.com", "udp2.example.com"]))
+        .with_srv(srv_record("_sips._tcp.example.com", &["alias.example.com"]))
+        .with_addr(addr_record("udp1.example.com"))
+        .with_addr(addr_record("alias.example.com"))
+        .with_cname("alias.example.com".into(), "tls.example.net".into());
+
+    let problems = Linter::new(dns_client).lint("example.com".into()).await.problems;
+
+    assert_eq!(
+        problems,
+        vec![
+            Problem::SrvTargetWithoutAddress {
+                domain: SrvDomain::try_from("_sip._udp.example.com").unwrap(),
+                target: "udp2.example.com".into()
+            },
+            Problem::SrvTargetIsAlias {
+                domain: SrvDomain::try_from("_sips._tcp.example.com").unwrap(),
+                target: "alias.example.com".into(),
+                canonical: "tls.example.net".into()
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reports_missing_records() {
+    let problems = Linter::new(StaticDnsClient::new()).lint("example.com".into()).await.problems;
+    assert_eq!(problems, vec![Problem::NoRecords("example.com".into())]);
+
+    //the A/AAAA fallback is enough
+    let dns_client = StaticDnsClient::new().with_addr(addr_record("example.com"));
+    let problems = Linter::new(dns_client).lint("example.com".into()).await.problems;
+    assert_eq!(problems, vec![]);
+}
+
+#[tokio::test]
+async fn reports_failed_queries() {
+    let dns_client =
+        FaultyDnsClient::new(dns_client(), 0).with_fault(QueryType::Srv, 1.0, Fault::ServFail);
+
+    let problems = Linter::new(dns_client).lint("example.com".into()).await.problems;
+
+    assert_eq!(problems.len(), 2);
+    assert!(problems
+        .iter()
+        .all(|problem| matches!(problem, Problem::QueryFailed { query_type: QueryType::Srv, .. })));
+}
+
+#[tokio::test]
+async fn queries_each_name_once() {
+    let dns_client = SpyDnsClient::new(dns_client().with_naptr(NaptrRecord {
+        domain: "example.com".into(),
+        entries: vec![
+            naptr_entry(NaptrServices::SipsD2t, "_sips._tcp.example.com"),
+            naptr_entry(NaptrServices::SipsD2t, "_sips._tcp.example.com"),
+            naptr_entry(NaptrServices::SipD2u, "_sip._udp.example.com"),
+        ],
+        additional_srvs: vec![],
+    }))
+    .expect(QueryType::Naptr, 1)
+    .expect(QueryType::Srv, 2)
+    .expect(QueryType::Cname, 1)
+    .expect(QueryType::Addr, 1);
+
+    Linter::new(dns_client.clone()).lint("example.com".into()).await;
+
+    dns_client.verify();
+}
+
+//TLS and UDP, all reachable, both services end up on the same host
+fn dns_client() -> StaticDnsClient {
+    StaticDnsClient::new()
+        .with_naptr(NaptrRecord {
+            domain: "example.com".into(),
+            entries: vec![
+                naptr_entry(NaptrServices::SipsD2t, "_sips._tcp.example.com"),
+                naptr_entry(NaptrServices::SipD2u, "_sip._udp.example.com"),
+            ],
+            additional_srvs: vec![],
+        })
+        .with_srv(srv_record("_sips._tcp.example.com", &["sip.example.com"]))
+        .with_srv(srv_record("_sip._udp.example.com", &["sip.example.com"]))
+        .with_addr(addr_record("sip.example.com"))
+}
+
+fn naptr_entry(services: NaptrServices, replacement: &str) -> NaptrEntry {
+    NaptrEntry {
+        order: 10,
+        preference: 10,
+        flags: NaptrFlags::S,
+        services,
+        regexp: vec![],
+        replacement: replacement.into(),
+    }
+}
+
+fn srv_record(domain: &str, targets: &[&str]) -> SrvRecord {
+    SrvRecord {
+        domain: SrvDomain::try_from(domain).unwrap(),
+        entries: targets
+            .iter()
+            .map(|target| SrvEntry {
+                priority: 10,
+                weight: 10,
+                port: 5060.into(),
+                target: (*target).into(),
+            })
+            .collect(),
+        additional_addrs: vec![],
+    }
+}
+
+fn addr_record(domain: &str) -> AddrRecord {
+    AddrRecord { domain: domain.into(), ip_addrs: vec!["10.0.0.1".parse().unwrap()] }
+}
@@ -25,6 +25,7 @@ async fn context_lookup() {
         port: None,
         dns_client: dns_client.clone(),
         supported_transports: rsip_dns::SupportedTransports::any(),
+        ..Default::default()
     };
 
     let mut lookup = Lookup::from(context);
@@ -43,6 +44,84 @@ async fn context_lookup() {
     assert_eq!(lookup.resolve_next().await, None);
 }
 
+#[tokio::test]
+async fn context_lookup_server_name() {
+    let (srv_map, a_records) = setup_dns_state();
+    let dns_config =
+        CustomDnsConfig { naptr: NaptrConfig::Panic, srv: srv_map.into(), a: a_records.into() };
+
+    let context = Context::new("example.com".into(), CustomDnsClient::from(dns_config))
+        .with_secure(true)
+        .with_transport(rsip::Transport::Tcp);
+
+    let mut lookup = Lookup::from(context);
+    let mut targets = vec![];
+    while let Some(target) = lookup.resolve_next().await {
+        targets.push(target);
+    }
+
+    assert!(targets.iter().all(|target| target.server_name == Some("example.com".into())));
+    assert_eq!(
+        targets.iter().map(|target| target.srv_target.clone()).collect::<Vec<_>>(),
+        vec![
+            Some("tcp-server1.example.com".into()),
+            Some("tcp-server1.example.com".into()),
+            Some("tcp-server2.example.com".into()),
+            Some("tcp-server2.example.com".into()),
+            //the A/AAAA fallback
+            None,
+            None,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn context_lookup_without_server_name() {
+    let (srv_map, a_records) = setup_dns_state();
+    let dns_config =
+        CustomDnsConfig { naptr: NaptrConfig::Panic, srv: srv_map.into(), a: a_records.into() };
+
+    let context = Context::new("example.com".into(), CustomDnsClient::from(dns_config))
+        .with_transport(rsip::Transport::Tcp);
+
+    let target = Lookup::from(context).resolve_next().await.unwrap();
+
+    assert_eq!(target.transport, rsip::Transport::Tcp);
+    assert_eq!(target.server_name, None);
+    assert_eq!(target.srv_target, None);
+}
+
+#[tokio::test]
+async fn context_lookup_with_unavailable_service() {
+    let mut srv_map = SrvMap::new();
+    srv_map.insert(
+        SrvDomain::try_from("_sip._udp.example.com").unwrap(),
+        vec![(0, 0, 5060.into(), ".".into())],
+    );
+    let mut a_records = ARecords::new();
+    a_records.insert("example.com".into(), vec![Randomize::random()]);
+
+    let dns_config =
+        CustomDnsConfig { naptr: NaptrConfig::Panic, srv: srv_map.into(), a: a_records.into() };
+
+    let context = Context::new("example.com".into(), CustomDnsClient::from(dns_config))
+        .with_transport(rsip::Transport::Udp);
+
+    let mut lookup = Lookup::from(context);
+
+    assert_eq!(lookup.resolve_next().await, None);
+
+    let trace = lookup.trace();
+    assert!(trace.ends_with(&[
+        TraceEvent::SrvUnavailable(SrvDomain::try_from("_sip._udp.example.com").unwrap()),
+        TraceEvent::AddrFallbackSkipped {
+            domain: "example.com".into(),
+            transport: rsip::Transport::Udp
+        }
+    ]));
+    assert!(!trace.iter().any(|event| matches!(event, TraceEvent::AddrQuery(_))));
+}
+
 fn setup_dns_state() -> (SrvMap, ARecords) {
     let mut srv_map = SrvMap::new();
     srv_map.insert(
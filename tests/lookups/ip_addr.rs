@@ -1,4 +1,3 @@
-use crate::support::PanicDnsClient;
 use rsip_dns::*;
 use std::net::IpAddr;
 use testing_utils::Randomize;
@@ -16,10 +15,11 @@ async fn context_lookup() {
     );
     assert!(matches!(lookup, Lookup::IpAddr { .. }));
 
-    let Target { ip_addr, port, transport } = lookup.resolve_next().await.unwrap();
+    let Target { ip_addr, port, transport, provenance, .. } = lookup.resolve_next().await.unwrap();
     assert_eq!(ip_addr, host_ip_addr);
     assert_eq!(port, 5060.into());
     assert_eq!(transport, rsip::Transport::Udp);
+    assert_eq!(provenance.steps(), &[ProvenanceStep::IpAddr(host_ip_addr)]);
 
     assert!(lookup.resolve_next().await.is_none());
 }
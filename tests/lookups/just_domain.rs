@@ -24,6 +24,7 @@ async fn context_lookup() {
         port: None,
         dns_client: dns_client.clone(),
         supported_transports: rsip_dns::SupportedTransports::any(),
+        ..Default::default()
     };
 
     let mut lookup = Lookup::from(context);
@@ -63,6 +64,53 @@ async fn context_lookup() {
     assert!(lookup.resolve_next().await.is_none());
 }
 
+#[tokio::test]
+async fn context_lookup_trace() {
+    let (naptr_map, srv_map, a_records) = setup_dns_state();
+    let config = CustomDnsConfig {
+        naptr: naptr_map.into(),
+        srv: srv_map.into(),
+        a: a_records.clone().into(),
+    };
+
+    let context =
+        Context::new("example.com".into(), CustomDnsClient::from(config)).with_secure(true);
+
+    let mut lookup = Lookup::from(context);
+    let target = lookup.resolve_next().await.unwrap();
+    let ip_addr = a_records.get(&Domain::from("tcp-server1.example.com")).unwrap()[0];
+
+    //the certificate is checked against the domain of the uri, not the SRV target
+    assert_eq!(target.server_name, Some("example.com".into()));
+    assert_eq!(target.srv_target, Some("tcp-server1.example.com".into()));
+    assert_eq!(
+        target.provenance.to_string(),
+        format!(
+            "NAPTR example.com SIPS+D2T -> SRV _sips._tcp.example.com priority 100 weight 5 \
+            tcp-server1.example.com:10000 -> {} tcp-server1.example.com {}",
+            match ip_addr {
+                std::net::IpAddr::V4(_) => "A",
+                std::net::IpAddr::V6(_) => "AAAA",
+            },
+            ip_addr
+        )
+    );
+
+    let trace = lookup.trace();
+    assert_eq!(trace.first(), Some(&TraceEvent::NaptrQuery("example.com".into())));
+    //SIP+D2U is not a secure transport
+    assert!(trace.iter().any(|event| matches!(
+        event,
+        TraceEvent::NaptrEntrySkipped {
+            entry: NaptrEntry { services: NaptrServices::SipD2u, .. },
+            reason: SkipReason::UnsupportedService
+        }
+    )));
+    assert!(trace.contains(&TraceEvent::SrvQuery("_sips._tcp.example.com".try_into().unwrap())));
+    assert!(trace.contains(&TraceEvent::AddrQuery("tcp-server1.example.com".into())));
+    assert_eq!(trace.last(), Some(&TraceEvent::Target(target)));
+}
+
 fn setup_dns_state() -> (NaptrMap, SrvMap, ARecords) {
     let mut naptr_map = NaptrMap::new();
     naptr_map.insert(
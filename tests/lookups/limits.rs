@@ -0,0 +1,57 @@
+use super::{context_for, ZONE};
+use rsip_dns::*;
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+async fn ip_addrs_of<C: DnsClient>(lookup: &mut Lookup<C>) -> Vec<IpAddr> {
+    let mut ip_addrs = vec![];
+    while let Some(target) = lookup.resolve_next().await {
+        ip_addrs.push(target.ip_addr);
+    }
+    ip_addrs
+}
+
+#[tokio::test]
+async fn stops_when_query_budget_is_exhausted() {
+    let dns_client = SpyDnsClient::new(ZoneFileDnsClient::parse(ZONE).unwrap());
+    let limits = Limits { max_queries: Some(3), ..Default::default() };
+    let mut lookup = Lookup::from(context_for(dns_client.clone()).with_limits(limits));
+
+    //NAPTR, SRV and A for sip1, the A query for sip2 is over budget
+    assert_eq!(ip_addrs_of(&mut lookup).await, vec!["10.0.0.1".parse::<IpAddr>().unwrap()]);
+    assert_eq!(lookup.stop_reason(), Some(StopReason::QueryBudgetExhausted));
+    assert_eq!(dns_client.calls().len(), 3);
+    assert_eq!(
+        lookup.trace().iter().filter(|event| matches!(event, TraceEvent::Stopped(_))).count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn stops_when_deadline_is_reached() {
+    let dns_client = FaultyDnsClient::new(ZoneFileDnsClient::parse(ZONE).unwrap(), 0)
+        .with_script(QueryType::Addr, vec![vec![], vec![Fault::Latency(Duration::from_secs(5))]]);
+    let limits = Limits { deadline: Some(Duration::from_millis(200)), ..Default::default() };
+    let mut lookup = Lookup::from(context_for(dns_client).with_limits(limits));
+
+    let started_at = Instant::now();
+    assert_eq!(ip_addrs_of(&mut lookup).await, vec!["10.0.0.1".parse::<IpAddr>().unwrap()]);
+    assert!(started_at.elapsed() < Duration::from_secs(1));
+    assert_eq!(lookup.stop_reason(), Some(StopReason::DeadlineExceeded));
+    assert_eq!(lookup.trace().last(), Some(&TraceEvent::Stopped(StopReason::DeadlineExceeded)));
+}
+
+#[tokio::test]
+async fn does_not_stop_within_limits() {
+    let dns_client = ZoneFileDnsClient::parse(ZONE).unwrap();
+    let limits = Limits { max_queries: Some(10), deadline: Some(Duration::from_secs(5)) };
+    let mut lookup = Lookup::from(context_for(dns_client).with_limits(limits));
+
+    assert_eq!(
+        ip_addrs_of(&mut lookup).await,
+        vec!["10.0.0.1".parse::<IpAddr>().unwrap(), "10.0.0.2".parse().unwrap()]
+    );
+    assert_eq!(lookup.stop_reason(), None);
+}
@@ -1,6 +1,6 @@
 macro_rules! assert_lookup {
     ($lookup:expr, $a_records:expr, $transport:ident, $port:expr, $a_domain:expr, $index:ident) => {
-        let Target { ip_addr, port, transport } = $lookup.resolve_next().await.unwrap();
+        let Target { ip_addr, port, transport, .. } = $lookup.resolve_next().await.unwrap();
         assert_eq!(transport, $transport);
         assert_eq!(port, $port.into());
         assert_eq!(
@@ -10,14 +10,44 @@ macro_rules! assert_lookup {
     };
 }
 
-use rsip::{Domain, Error, Port};
-use rsip_dns::{records::*, DnsClient};
+use rsip::{Domain, Error, Port, Transport};
+use rsip_dns::{records::*, Context, DnsClient, SupportedTransports};
 use std::{collections::HashMap, net::IpAddr};
 
 pub mod domain_with_port;
 pub mod domain_with_transport;
 pub mod ip_addr;
 pub mod just_domain;
+pub mod limits;
+pub mod observer;
+pub mod plan;
+pub mod prefetch;
+pub mod resolve_all;
+pub mod target_memo;
+#[cfg(feature = "tracing")]
+pub mod tracing;
+
+//example.com over UDP only, with two SRV targets, shared by the tests of the lookup features
+pub const ZONE: &str = r#"
+$ORIGIN example.com.
+_sip._udp  SRV 10 10 5060 sip1
+           SRV 20 10 5060 sip2
+sip1       A 10.0.0.1
+sip2       A 10.0.0.2
+"#;
+
+//a lookup of example.com over UDP only, hence with a single SRV branch
+pub fn context_for<C: DnsClient>(dns_client: C) -> Context<C> {
+    context_with_transports(dns_client, vec![Transport::Udp])
+}
+
+pub fn context_with_transports<C: DnsClient>(
+    dns_client: C,
+    transports: Vec<Transport>,
+) -> Context<C> {
+    Context::new("example.com".into(), dns_client)
+        .with_supported_transports(SupportedTransports::only(transports))
+}
 
 #[derive(Clone, Default)]
 pub struct CustomDnsClient {
@@ -82,6 +112,7 @@ fn naptr_records_from_naptr_map(naptr_map: NaptrMap) -> NaptrRecords {
                         regexp: vec![],
                     })
                     .collect::<Vec<NaptrEntry>>(),
+                additional_srvs: vec![],
             },
         );
     }
@@ -106,6 +137,7 @@ fn srv_records_from_srv_map(srv_map: SrvMap) -> SrvRecords {
                         target: tuple.3,
                     })
                     .collect::<Vec<SrvEntry>>(),
+                additional_addrs: vec![],
             },
         );
     }
@@ -0,0 +1,267 @@
+use super::{context_for, context_with_transports, ZONE};
+#[cfg(feature = "metrics")]
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use rsip::Transport::*;
+use rsip_dns::{records::*, *};
+#[cfg(feature = "metrics")]
+use std::collections::BTreeMap;
+use std::{
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+//records every call as a line, leaving out the latencies
+#[derive(Debug, Default)]
+struct RecordingObserver(Mutex<Vec<String>>);
+
+impl RecordingObserver {
+    fn push(&self, call: String) {
+        self.0.lock().unwrap().push(call)
+    }
+
+    fn calls(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Observer for RecordingObserver {
+    fn query_started(&self, query_type: QueryType, domain: &str) {
+        self.push(format!("started {:?} {}", query_type, domain))
+    }
+
+    fn query_ended(&self, query_type: QueryType, domain: &str, outcome: QueryOutcome, _: Duration) {
+        self.push(format!("ended {:?} {} {:?}", query_type, domain, outcome))
+    }
+
+    fn query_preresolved(&self, query_type: QueryType, domain: &str) {
+        self.push(format!("preresolved {:?} {}", query_type, domain))
+    }
+
+    fn branch_empty(&self, query_type: QueryType, domain: &str) {
+        self.push(format!("empty {:?} {}", query_type, domain))
+    }
+
+    fn target_yielded(&self, target: &Target, attempt: usize, _: Duration) {
+        self.push(format!("target {} {}", attempt, target.ip_addr))
+    }
+
+    fn target_succeeded(&self, attempt: usize) {
+        self.push(format!("succeeded {}", attempt))
+    }
+
+    fn lookup_exhausted(&self, targets: usize) {
+        self.push(format!("exhausted {}", targets))
+    }
+}
+
+#[tokio::test]
+async fn calls_the_observer_at_key_points() {
+    let observer = Arc::new(RecordingObserver::default());
+    let mut lookup = Lookup::from(context_for(ZoneFileDnsClient::parse(ZONE).unwrap()))
+        .with_observer(observer.clone());
+
+    assert!(lookup.resolve_next().await.is_some());
+    assert!(lookup.resolve_next().await.is_some());
+    lookup.report_success();
+    assert!(lookup.resolve_next().await.is_none());
+    assert!(lookup.resolve_next().await.is_none());
+
+    assert_eq!(
+        observer.calls(),
+        vec![
+            "started Naptr example.com",
+            "ended Naptr example.com Empty",
+            "empty Naptr example.com",
+            "started Srv _sip._udp.example.com",
+            "ended Srv _sip._udp.example.com Answered(2)",
+            "started Addr sip1.example.com",
+            "ended Addr sip1.example.com Answered(1)",
+            "target 1 10.0.0.1",
+            "started Addr sip2.example.com",
+            "ended Addr sip2.example.com Answered(1)",
+            "target 2 10.0.0.2",
+            "succeeded 2",
+            "started Addr example.com",
+            "ended Addr example.com Failed",
+            "empty Addr example.com",
+            "exhausted 2",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reports_empty_branches_when_prefetching() {
+    let zone = r#"
+$ORIGIN example.com.
+_sip._udp  SRV 10 10 5060 sip1
+sip1       A 10.0.0.1
+"#;
+    let observer = Arc::new(RecordingObserver::default());
+    let dns_client = ZoneFileDnsClient::parse(zone).unwrap();
+    let context = context_with_transports(dns_client, vec![Udp, Tcp]).with_prefetch(2);
+    let mut lookup = Lookup::from(context).with_observer(observer.clone());
+
+    while lookup.resolve_next().await.is_some() {}
+
+    //the SRV branch of TCP is prefetched while the one of UDP yields its target
+    let calls = observer.calls();
+    assert_eq!(
+        calls.iter().filter(|call| call.starts_with("empty")).collect::<Vec<_>>(),
+        vec![
+            "empty Naptr example.com",
+            "empty Srv _sip._tcp.example.com",
+            "empty Addr example.com"
+        ]
+    );
+    assert!(calls.contains(&"exhausted 1".to_string()));
+}
+
+#[tokio::test]
+async fn reports_success_through_target_memo() {
+    let observer = Arc::new(RecordingObserver::default());
+    let memo = TargetMemo::new();
+    let invite = TransactionKey::new("z9hG4bK-1", "call-1@example.com");
+    let lookup = Lookup::from(context_for(ZoneFileDnsClient::parse(ZONE).unwrap()))
+        .with_observer(observer.clone());
+
+    memo.target_for(&invite, lookup).await;
+    memo.report_failure(&invite).await;
+    memo.report_success(&invite).await;
+
+    let calls = observer.calls();
+    assert_eq!(calls.iter().filter(|call| call.starts_with("target")).count(), 2);
+    assert_eq!(calls.last().map(String::as_str), Some("succeeded 2"));
+}
+
+#[tokio::test]
+async fn reports_preresolved_records() {
+    let srv_domain = SrvDomain::try_from("_sip._udp.example.com").unwrap();
+    let dns_client = StaticDnsClient::new().with_naptr(NaptrRecord {
+        domain: "example.com".into(),
+        entries: vec![NaptrEntry {
+            order: 10,
+            preference: 10,
+            flags: NaptrFlags::S,
+            services: NaptrServices::SipD2u,
+            regexp: vec![],
+            replacement: srv_domain.to_string().into(),
+        }],
+        additional_srvs: vec![SrvRecord {
+            domain: srv_domain,
+            entries: vec![SrvEntry {
+                priority: 10,
+                weight: 10,
+                port: 5060.into(),
+                target: "sip1.example.com".into(),
+            }],
+            additional_addrs: vec![AddrRecord {
+                domain: "sip1.example.com".into(),
+                ip_addrs: vec!["10.0.0.1".parse().unwrap()],
+            }],
+        }],
+    });
+    let observer = Arc::new(RecordingObserver::default());
+    let mut lookup = Lookup::from(context_for(dns_client)).with_observer(observer.clone());
+
+    assert!(lookup.resolve_next().await.is_some());
+    assert_eq!(
+        observer.calls(),
+        vec![
+            "started Naptr example.com",
+            "ended Naptr example.com Answered(1)",
+            "preresolved Srv _sip._udp.example.com",
+            "preresolved Addr sip1.example.com",
+            "target 1 10.0.0.1",
+        ]
+    );
+}
+
+//never answers, so that its queries are only dropped
+#[derive(Debug, Clone)]
+struct HangingDnsClient;
+
+#[async_trait::async_trait]
+impl DnsClient for HangingDnsClient {
+    async fn naptr_lookup(&self, _domain: rsip::Domain) -> Option<NaptrRecord> {
+        futures::future::pending().await
+    }
+
+    async fn srv_lookup(&self, _domain: SrvDomain) -> Option<SrvRecord> {
+        futures::future::pending().await
+    }
+
+    async fn ip_lookup(&self, _domain: rsip::Domain) -> Result<AddrRecord, rsip::Error> {
+        futures::future::pending().await
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn reports_dropped_queries_as_cancelled() {
+    let observer = Arc::new(RecordingObserver::default());
+    let mut lookup = Lookup::from(context_for(HangingDnsClient)).with_observer(observer.clone());
+
+    let resolved = tokio::time::timeout(Duration::from_secs(1), lookup.resolve_next()).await;
+    assert!(resolved.is_err());
+    assert_eq!(
+        observer.calls(),
+        vec!["started Naptr example.com", "ended Naptr example.com Cancelled"]
+    );
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn records_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+
+    metrics::with_local_recorder(&recorder, || {
+        futures::executor::block_on(async {
+            let mut lookup = Lookup::from(context_for(ZoneFileDnsClient::parse(ZONE).unwrap()))
+                .with_observer(Arc::new(MetricsObserver::new()));
+
+            assert!(lookup.resolve_next().await.is_some());
+            lookup.report_success();
+            while lookup.resolve_next().await.is_some() {}
+        })
+    });
+
+    let metrics = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| {
+            let key = key.key();
+            let labels =
+                key.labels().map(|l| format!("{}={}", l.key(), l.value())).collect::<Vec<_>>();
+            let value = match value {
+                DebugValue::Counter(count) => count as f64,
+                DebugValue::Gauge(value) => value.into_inner(),
+                DebugValue::Histogram(values) => values.len() as f64,
+            };
+            (format!("{}{{{}}}", key.name(), labels.join(",")), value)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let expected = vec![
+        ("rsip_dns_empty_branches_total{record_type=addr}", 1.0),
+        ("rsip_dns_empty_branches_total{record_type=naptr}", 1.0),
+        ("rsip_dns_lookups_exhausted_total{}", 1.0),
+        ("rsip_dns_queries_in_flight{record_type=addr}", 0.0),
+        ("rsip_dns_queries_in_flight{record_type=naptr}", 0.0),
+        ("rsip_dns_queries_in_flight{record_type=srv}", 0.0),
+        ("rsip_dns_queries_total{record_type=addr,outcome=answered}", 2.0),
+        ("rsip_dns_queries_total{record_type=addr,outcome=failed}", 1.0),
+        ("rsip_dns_queries_total{record_type=naptr,outcome=empty}", 1.0),
+        ("rsip_dns_queries_total{record_type=srv,outcome=answered}", 1.0),
+        ("rsip_dns_query_duration_seconds{record_type=addr}", 3.0),
+        ("rsip_dns_query_duration_seconds{record_type=naptr}", 1.0),
+        ("rsip_dns_query_duration_seconds{record_type=srv}", 1.0),
+        ("rsip_dns_targets_tried{}", 1.0),
+        ("rsip_dns_time_to_first_target_seconds{}", 1.0),
+    ];
+    assert_eq!(
+        metrics,
+        expected.into_iter().map(|(key, value)| (key.to_string(), value)).collect()
+    );
+}
@@ -0,0 +1,50 @@
+use rsip::Transport::*;
+use rsip_dns::*;
+use std::convert::TryFrom;
+
+#[test]
+fn just_domain_plan() {
+    let context = Context::new("example.com".into(), PanicDnsClient)
+        .with_supported_transports(SupportedTransports::only(vec![Udp, Tcp]));
+
+    let plan = context.plan();
+
+    assert_eq!(
+        plan.steps(),
+        &[
+            PlannedStep::Naptr { domain: "example.com".into(), transports: vec![Udp, Tcp] },
+            PlannedStep::Srv(SrvDomain::try_from("_sip._udp.example.com").unwrap()),
+            PlannedStep::Srv(SrvDomain::try_from("_sip._tcp.example.com").unwrap()),
+            PlannedStep::AddrFallback {
+                domain: "example.com".into(),
+                port: 5060.into(),
+                transport: Udp
+            },
+        ]
+    );
+    assert_eq!(
+        plan.to_string(),
+        "1. NAPTR example.com for UDP, TCP\n\
+         2. SRV _sip._udp.example.com\n\
+         3. SRV _sip._tcp.example.com\n\
+         4. A/AAAA example.com port 5060 UDP (fallback)\n"
+    );
+}
+
+#[test]
+fn domain_with_transport_plan() {
+    let context =
+        Context::new("example.com".into(), PanicDnsClient).with_secure(true).with_transport(Tcp);
+
+    assert_eq!(
+        context.plan().steps(),
+        &[
+            PlannedStep::Srv(SrvDomain::try_from("_sips._tcp.example.com").unwrap()),
+            PlannedStep::AddrFallback {
+                domain: "example.com".into(),
+                port: 5061.into(),
+                transport: Tls
+            },
+        ]
+    );
+}
@@ -0,0 +1,109 @@
+use super::context_for;
+use rsip::{Domain, Error};
+use rsip_dns::{records::*, *};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::time::Instant;
+
+const ZONE: &str = r#"
+$ORIGIN example.com.
+_sip._udp  SRV 10 10 5060 sip1
+           SRV 20 10 5060 sip2
+           SRV 30 10 5060 sip3
+sip1       A 10.0.0.1
+sip1       A 10.0.0.2
+sip2       A 10.0.0.3
+sip3       A 10.0.0.4
+"#;
+
+async fn targets_of<C: DnsClient>(mut lookup: Lookup<C>) -> Vec<Target> {
+    let mut targets = vec![];
+    while let Some(target) = lookup.resolve_next().await {
+        targets.push(target);
+    }
+    targets
+}
+
+fn addr_queries_of<C: DnsClient>(lookup: &Lookup<C>) -> usize {
+    lookup.trace().iter().filter(|event| matches!(event, TraceEvent::AddrQuery(_))).count()
+}
+
+#[tokio::test]
+async fn yields_targets_in_the_same_order() {
+    let dns_client = ZoneFileDnsClient::parse(ZONE).unwrap();
+
+    let lazy = targets_of(Lookup::from(context_for(dns_client.clone()).with_prefetch(0))).await;
+    let prefetched = targets_of(Lookup::from(context_for(dns_client).with_prefetch(2))).await;
+
+    assert_eq!(prefetched, lazy);
+    assert_eq!(prefetched.len(), 4);
+}
+
+#[tokio::test]
+async fn sends_upcoming_queries_ahead_of_time() {
+    let dns_client = ZoneFileDnsClient::parse(ZONE).unwrap();
+
+    let mut lookup = Lookup::from(context_for(dns_client.clone()).with_prefetch(0));
+    lookup.resolve_next().await.unwrap();
+    assert_eq!(addr_queries_of(&lookup), 1);
+
+    let mut lookup = Lookup::from(context_for(dns_client.clone()).with_prefetch(1));
+    lookup.resolve_next().await.unwrap();
+    assert_eq!(addr_queries_of(&lookup), 2);
+
+    let mut lookup = Lookup::from(context_for(dns_client).with_prefetch(2));
+    lookup.resolve_next().await.unwrap();
+    assert_eq!(addr_queries_of(&lookup), 3);
+}
+
+//counts the A/AAAA queries in flight, each one taking 300ms (of the paused tokio clock)
+#[derive(Debug, Clone)]
+struct SlowAddrDnsClient {
+    inner: ZoneFileDnsClient,
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl DnsClient for SlowAddrDnsClient {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        self.inner.naptr_lookup(domain).await
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        self.inner.srv_lookup(domain).await
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        self.inner.ip_lookup(domain).await
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn runs_prefetched_queries_concurrently() {
+    let dns_client = SlowAddrDnsClient {
+        inner: ZoneFileDnsClient::parse(ZONE).unwrap(),
+        in_flight: Default::default(),
+        max_in_flight: Default::default(),
+    };
+
+    let mut lookup = Lookup::from(context_for(dns_client.clone()).with_prefetch(2));
+
+    //the A/AAAA queries of the 3 SRV targets take 300ms each, lazily that would be 900ms
+    let started_at = Instant::now();
+    for _ in 0..4 {
+        lookup.resolve_next().await.unwrap();
+    }
+    assert_eq!(dns_client.max_in_flight.load(Ordering::SeqCst), 3);
+    assert_eq!(started_at.elapsed(), Duration::from_millis(300));
+}
@@ -0,0 +1,122 @@
+use super::context_with_transports;
+use rsip::{Domain, Error, Transport::*};
+use rsip_dns::{records::*, *};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::time::Instant;
+
+const ZONE: &str = r#"
+$ORIGIN example.com.
+@          NAPTR 10 10 "S" "SIP+D2U" "" _sip._udp
+           NAPTR 20 10 "S" "SIP+D2T" "" _sip._tcp
+_sip._udp  SRV 10 10 5060 sip1
+           SRV 20 10 5060 sip2
+_sip._tcp  SRV 10 10 5060 sip3
+           SRV 20 10 5060 sip4
+sip1       A 10.0.0.1
+sip1       A 10.0.0.2
+sip2       A 10.0.0.3
+sip3       A 10.0.0.4
+sip4       A 10.0.0.5
+"#;
+
+//counts the queries in flight, each one taking 100ms (of the paused tokio clock)
+#[derive(Debug, Clone)]
+struct SlowDnsClient {
+    inner: ZoneFileDnsClient,
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+impl SlowDnsClient {
+    fn new() -> Self {
+        Self {
+            inner: ZoneFileDnsClient::parse(ZONE).unwrap(),
+            in_flight: Default::default(),
+            max_in_flight: Default::default(),
+        }
+    }
+
+    async fn delay(&self) {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[async_trait::async_trait]
+impl DnsClient for SlowDnsClient {
+    async fn naptr_lookup(&self, domain: Domain) -> Option<NaptrRecord> {
+        self.delay().await;
+        self.inner.naptr_lookup(domain).await
+    }
+
+    async fn srv_lookup(&self, domain: SrvDomain) -> Option<SrvRecord> {
+        self.delay().await;
+        self.inner.srv_lookup(domain).await
+    }
+
+    async fn ip_lookup(&self, domain: Domain) -> Result<AddrRecord, Error> {
+        self.delay().await;
+        self.inner.ip_lookup(domain).await
+    }
+}
+
+async fn lazy_targets_of<C: DnsClient>(mut lookup: Lookup<C>) -> Vec<Target> {
+    let mut targets = vec![];
+    while let Some(target) = lookup.resolve_next().await {
+        targets.push(target);
+    }
+    targets
+}
+
+#[tokio::test]
+async fn resolves_all_targets_in_order() {
+    let dns_client = ZoneFileDnsClient::parse(ZONE).unwrap();
+
+    let lazy =
+        lazy_targets_of(Lookup::from(context_with_transports(dns_client.clone(), vec![Udp, Tcp])))
+            .await;
+    let mut lookup = Lookup::from(context_with_transports(dns_client, vec![Udp, Tcp]));
+    let targets = lookup.resolve_all(4).await;
+
+    assert_eq!(targets, lazy);
+    assert!(lookup.resolve_next().await.is_none());
+}
+
+#[tokio::test(start_paused = true)]
+async fn resolves_in_parallel_up_to_max_concurrency() {
+    let dns_client = SlowDnsClient::new();
+    let lazy_started_at = Instant::now();
+    let lazy =
+        lazy_targets_of(Lookup::from(context_with_transports(dns_client.clone(), vec![Udp, Tcp])))
+            .await;
+    let lazy_elapsed = lazy_started_at.elapsed();
+    assert_eq!(dns_client.max_in_flight.load(Ordering::SeqCst), 1);
+
+    let dns_client = SlowDnsClient::new();
+    let started_at = Instant::now();
+    let targets = Lookup::from(context_with_transports(dns_client.clone(), vec![Udp, Tcp]))
+        .resolve_all(2)
+        .await;
+
+    assert_eq!(targets, lazy);
+    assert_eq!(dns_client.max_in_flight.load(Ordering::SeqCst), 2);
+    assert!(started_at.elapsed() < lazy_elapsed);
+
+    let dns_client = SlowDnsClient::new();
+    let started_at = Instant::now();
+    let targets = Lookup::from(context_with_transports(dns_client.clone(), vec![Udp, Tcp]))
+        .resolve_all(10)
+        .await;
+
+    assert_eq!(targets, lazy);
+    assert!(dns_client.max_in_flight.load(Ordering::SeqCst) > 2);
+    assert!(started_at.elapsed() < lazy_elapsed / 2);
+}
@@ -0,0 +1,106 @@
+use super::{context_for, ZONE};
+use rsip::headers::*;
+use rsip_dns::*;
+use std::{convert::TryFrom, net::IpAddr, time::Duration};
+
+fn ip_addr_of(target: Option<Target>) -> IpAddr {
+    target.unwrap().ip_addr
+}
+
+#[tokio::test]
+async fn pins_target_per_transaction() {
+    let dns_client = SpyDnsClient::new(ZoneFileDnsClient::parse(ZONE).unwrap());
+    let memo = TargetMemo::new();
+    let invite = TransactionKey::new("z9hG4bK-1", "call-1@example.com");
+
+    let target = memo.target_for(&invite, context_for(dns_client.clone())).await;
+    assert_eq!(ip_addr_of(target), "10.0.0.1".parse::<IpAddr>().unwrap());
+    let queries = dns_client.calls().len();
+
+    //a CANCEL or a non-2xx ACK of the same transaction, the context is not used at all
+    let target = memo.target_for(&invite, context_for(dns_client.clone())).await;
+    assert_eq!(ip_addr_of(target), "10.0.0.1".parse::<IpAddr>().unwrap());
+    assert_eq!(dns_client.calls().len(), queries);
+
+    let other = TransactionKey::new("z9hG4bK-2", "call-1@example.com");
+    assert!(memo.pinned(&other).await.is_none());
+    assert!(memo.target_for(&other, context_for(dns_client)).await.is_some());
+    assert_eq!(memo.len(), 2);
+}
+
+#[tokio::test]
+async fn moves_forward_on_failure_only() {
+    let memo = TargetMemo::new();
+    let invite = TransactionKey::new("z9hG4bK-1", "call-1@example.com");
+    let dns_client = ZoneFileDnsClient::parse(ZONE).unwrap();
+
+    memo.target_for(&invite, context_for(dns_client.clone())).await;
+    let target = memo.report_failure(&invite).await;
+
+    assert_eq!(ip_addr_of(target), "10.0.0.2".parse::<IpAddr>().unwrap());
+    assert_eq!(
+        ip_addr_of(memo.target_for(&invite, context_for(dns_client)).await),
+        "10.0.0.2".parse::<IpAddr>().unwrap()
+    );
+    assert_eq!(ip_addr_of(memo.pinned(&invite).await), "10.0.0.2".parse::<IpAddr>().unwrap());
+
+    assert!(memo.report_failure(&TransactionKey::new("unknown", "unknown")).await.is_none());
+    memo.remove(&invite);
+    assert!(memo.is_empty());
+}
+
+#[tokio::test]
+async fn expires_entries() {
+    let memo = TargetMemo::new().with_ttl(Duration::from_millis(50));
+    let invite = TransactionKey::new("z9hG4bK-1", "call-1@example.com");
+    let dns_client = ZoneFileDnsClient::parse(ZONE).unwrap();
+
+    memo.target_for(&invite, context_for(dns_client.clone())).await;
+    memo.report_failure(&invite).await;
+    assert_eq!(memo.len(), 1);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(memo.is_empty());
+    assert_eq!(
+        ip_addr_of(memo.target_for(&invite, context_for(dns_client)).await),
+        "10.0.0.1".parse::<IpAddr>().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn keeps_entries_created_again_after_removal() {
+    let memo = TargetMemo::new().with_ttl(Duration::from_millis(100));
+    let invite = TransactionKey::new("z9hG4bK-1", "call-1@example.com");
+    let dns_client = ZoneFileDnsClient::parse(ZONE).unwrap();
+
+    memo.target_for(&invite, context_for(dns_client.clone())).await;
+    memo.remove(&invite);
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    memo.target_for(&invite, context_for(dns_client)).await;
+
+    //the first entry would have expired by now, the second one has not
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(memo.len(), 1);
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(memo.is_empty());
+}
+
+#[test]
+fn transaction_key_from_request() {
+    let request = rsip::Request {
+        method: rsip::Method::Cancel,
+        uri: rsip::Uri::try_from("sip:alice@example.com").unwrap(),
+        version: Default::default(),
+        headers: vec![
+            Via::new("SIP/2.0/UDP 10.0.0.10:5060;branch=z9hG4bK776asdhds").into(),
+            CallId::new("a84b4c76e66710@pc33.example.com").into(),
+        ]
+        .into(),
+        body: vec![],
+    };
+
+    assert_eq!(
+        TransactionKey::try_from(&request).unwrap(),
+        TransactionKey::new("z9hG4bK776asdhds", "a84b4c76e66710@pc33.example.com")
+    );
+}
@@ -0,0 +1,174 @@
+use super::{context_for, ZONE};
+use rsip_dns::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context as LayerContext, prelude::*, registry::LookupSpan, Layer};
+
+//a span (or an event) as recorded by the collector, along with the name of its parent span
+#[derive(Debug, Clone, Default)]
+struct Recorded {
+    name: String,
+    parent: Option<String>,
+    fields: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Default)]
+struct Collector {
+    spans: Arc<Mutex<Vec<Recorded>>>,
+    ids: Arc<Mutex<HashMap<span::Id, usize>>>,
+    events: Arc<Mutex<Vec<Recorded>>>,
+}
+
+struct Fields<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name().into(), format!("{:?}", value));
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Collector {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: LayerContext<'_, S>) {
+        let mut recorded = Recorded {
+            name: attrs.metadata().name().into(),
+            parent: ctx.span(id).and_then(|span| span.parent()).map(|p| p.name().into()),
+            ..Default::default()
+        };
+        attrs.record(&mut Fields(&mut recorded.fields));
+
+        let mut spans = self.spans.lock().unwrap();
+        self.ids.lock().unwrap().insert(id.clone(), spans.len());
+        spans.push(recorded);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, _: LayerContext<'_, S>) {
+        let index = self.ids.lock().unwrap()[id];
+        values.record(&mut Fields(&mut self.spans.lock().unwrap()[index].fields));
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: LayerContext<'_, S>) {
+        let mut recorded = Recorded {
+            parent: ctx.event_span(event).map(|span| span.name().into()),
+            ..Default::default()
+        };
+        event.record(&mut Fields(&mut recorded.fields));
+        recorded.name = recorded.fields.remove("message").unwrap_or_default();
+
+        self.events.lock().unwrap().push(recorded);
+    }
+}
+
+impl Collector {
+    fn spans(&self) -> Vec<Recorded> {
+        self.spans.lock().unwrap().clone()
+    }
+
+    fn events(&self) -> Vec<String> {
+        self.events.lock().unwrap().iter().map(|event| event.name.clone()).collect()
+    }
+}
+
+fn field<'a>(recorded: &'a Recorded, name: &str) -> Option<&'a str> {
+    recorded.fields.get(name).map(String::as_str)
+}
+
+#[tokio::test]
+async fn opens_a_span_per_lookup_and_query() {
+    let collector = Collector::default();
+    let _guard = tracing_subscriber::registry().with(collector.clone()).set_default();
+
+    let mut lookup = Lookup::from(context_for(ZoneFileDnsClient::parse(ZONE).unwrap()));
+    while lookup.resolve_next().await.is_some() {}
+
+    let spans = collector.spans();
+    let names = spans.iter().map(|span| span.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["lookup", "dns_query", "dns_query", "dns_query", "dns_query", "dns_query"]
+    );
+
+    let lookup_span = &spans[0];
+    assert_eq!(field(lookup_span, "host"), Some("example.com"));
+    assert_eq!(lookup_span.parent, None);
+
+    let queries = spans[1..]
+        .iter()
+        .map(|span| {
+            assert_eq!(span.parent.as_deref(), Some("lookup"));
+            assert!(span.fields.contains_key("latency_ms"));
+            (
+                field(span, "record_type").unwrap(),
+                field(span, "domain").unwrap(),
+                field(span, "answers"),
+                field(span, "outcome").unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        queries,
+        vec![
+            ("Naptr", "example.com", Some("0"), "empty"),
+            ("Srv", "_sip._udp.example.com", Some("2"), "answered"),
+            ("Addr", "sip1.example.com", Some("1"), "answered"),
+            ("Addr", "sip2.example.com", Some("1"), "answered"),
+            ("Addr", "example.com", None, "failed"),
+        ]
+    );
+    assert_eq!(
+        collector.events(),
+        vec![
+            "SRV branch started",
+            "target yielded",
+            "target yielded",
+            "A/AAAA fallback started",
+            "query failed"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn records_fallbacks_and_failed_queries() {
+    let collector = Collector::default();
+    let _guard = tracing_subscriber::registry().with(collector.clone()).set_default();
+
+    let mut lookup = Lookup::from(context_for(StaticDnsClient::new()));
+    assert!(lookup.resolve_next().await.is_none());
+
+    let addr_query = collector.spans().pop().unwrap();
+    assert_eq!(field(&addr_query, "record_type"), Some("Addr"));
+    assert_eq!(field(&addr_query, "outcome"), Some("failed"));
+    assert_eq!(field(&addr_query, "answers"), None);
+    assert_eq!(
+        collector.events(),
+        vec!["SRV branch started", "A/AAAA fallback started", "query failed"]
+    );
+}
+
+#[tokio::test]
+async fn records_why_the_lookup_stopped() {
+    let collector = Collector::default();
+    let _guard = tracing_subscriber::registry().with(collector.clone()).set_default();
+
+    let context = context_for(ZoneFileDnsClient::parse(ZONE).unwrap())
+        .with_limits(Limits { max_queries: Some(1), ..Default::default() });
+    let mut lookup = Lookup::from(context);
+    assert!(lookup.resolve_next().await.is_none());
+
+    assert_eq!(field(&collector.spans()[0], "stop_reason"), Some("QueryBudgetExhausted"));
+    //the remaining branches still start, but send no queries
+    assert_eq!(
+        collector.events(),
+        vec!["SRV branch started", "lookup stopped", "A/AAAA fallback started"]
+    );
+}
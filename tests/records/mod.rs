@@ -1 +1,5 @@
+pub mod naptr_record;
+#[cfg(feature = "serde")]
+pub mod serialization;
 pub mod srv_domain;
+pub mod srv_record;
@@ -0,0 +1,16 @@
+use rsip_dns::records::*;
+
+#[test]
+fn parses_naptr_flags() {
+    for (bytes, flags) in [
+        (&b"S"[..], NaptrFlags::S),
+        (&b"A"[..], NaptrFlags::A),
+        (&b"U"[..], NaptrFlags::U),
+        (&b"P"[..], NaptrFlags::P),
+    ] {
+        assert_eq!(NaptrFlags::from(bytes), flags);
+        assert_eq!(flags.as_bytes(), bytes);
+    }
+
+    assert_eq!(NaptrFlags::from(&b"SU"[..]), NaptrFlags::Other(b"SU".to_vec()));
+}
@@ -0,0 +1,148 @@
+use rsip::{Domain, Transport};
+use rsip_dns::{records::*, *};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+use std::{convert::TryFrom, fmt::Debug, net::IpAddr};
+use testing_utils::Randomize;
+
+fn assert_round_trip<T: Serialize + DeserializeOwned + Debug + PartialEq>(value: T) {
+    let serialized = serde_json::to_string(&value).unwrap();
+
+    assert_eq!(serde_json::from_str::<T>(&serialized).unwrap(), value, "{}", serialized);
+}
+
+//the protocol of a SrvDomain is always the insecure variant of the transport (see From impl)
+fn srv_domain() -> SrvDomain {
+    SrvDomain::from((SrvService::Sip, Domain::random(), Transport::random()))
+}
+
+#[test]
+fn records_round_trip() {
+    for _ in 0..50 {
+        assert_round_trip(srv_domain());
+        assert_round_trip(SrvEntry::random());
+        assert_round_trip(SrvRecord { domain: srv_domain(), ..SrvRecord::random() });
+        assert_round_trip(NaptrEntry::random());
+        assert_round_trip(AddrRecord {
+            domain: Randomize::random(),
+            ip_addrs: vec![Randomize::random(), Randomize::random()],
+        });
+    }
+
+    assert_round_trip(NaptrRecord {
+        entries: vec![NaptrEntry::random(), NaptrEntry::random()],
+        domain: "example.com".into(),
+        additional_srvs: vec![SrvRecord { domain: srv_domain(), ..SrvRecord::random() }],
+    });
+    for flags in [NaptrFlags::S, NaptrFlags::A, NaptrFlags::U, NaptrFlags::P] {
+        assert_round_trip(NaptrEntry { flags, ..NaptrEntry::random() });
+    }
+    assert_round_trip(SrvDomain::from((
+        SrvService::Other("xmpp-server".into()),
+        "example.com".into(),
+        Transport::Tcp,
+    )));
+}
+
+#[test]
+fn supported_transports_round_trip() {
+    assert_round_trip(SupportedTransports::any());
+    assert_round_trip(SupportedTransports::only(vec![Transport::Tls, Transport::Wss]));
+
+    assert_eq!(
+        serde_json::to_value(SupportedTransports::only(vec![Transport::Udp, Transport::TlsSctp]))
+            .unwrap(),
+        json!(["UDP", "TLS-SCTP"])
+    );
+    assert!(serde_json::from_value::<SupportedTransports>(json!(["UDP", "FOO"])).is_err());
+}
+
+#[test]
+fn target_round_trip() {
+    let ip_addr: IpAddr = "10.0.0.1".parse().unwrap();
+    let target = Target::new(
+        ip_addr,
+        5061.into(),
+        Transport::Tls,
+        vec![
+            ProvenanceStep::Naptr {
+                domain: "example.com".into(),
+                services: NaptrServices::SipsD2t,
+                replacement: SrvDomain::try_from("_sips._tcp.example.com").unwrap(),
+            },
+            ProvenanceStep::Srv {
+                domain: SrvDomain::try_from("_sips._tcp.example.com").unwrap(),
+                priority: 10,
+                weight: 5,
+                port: 5061.into(),
+                target: "sip1.example.com".into(),
+            },
+            ProvenanceStep::Addr { domain: "sip1.example.com".into(), ip_addr },
+        ]
+        .into(),
+    );
+
+    assert_round_trip(target.clone());
+    assert_round_trip(Target::from((ip_addr, 5060.into(), Transport::Udp)));
+    assert_round_trip(Target::new(
+        ip_addr,
+        5060.into(),
+        Transport::Udp,
+        vec![ProvenanceStep::IpAddr(ip_addr)].into(),
+    ));
+
+    assert_eq!(
+        serde_json::to_value(&target).unwrap(),
+        json!({
+            "ip_addr": "10.0.0.1",
+            "port": 5061,
+            "transport": "TLS",
+            "server_name": "example.com",
+            "srv_target": "sip1.example.com",
+            "provenance": [
+                {
+                    "naptr": {
+                        "domain": "example.com",
+                        "services": "SIPS+D2T",
+                        "replacement": "_sips._tcp.example.com"
+                    }
+                },
+                {
+                    "srv": {
+                        "domain": "_sips._tcp.example.com",
+                        "priority": 10,
+                        "weight": 5,
+                        "port": 5061,
+                        "target": "sip1.example.com"
+                    }
+                },
+                { "addr": { "domain": "sip1.example.com", "ip_addr": "10.0.0.1" } }
+            ]
+        })
+    );
+}
+
+#[test]
+fn naptr_entry_representation() {
+    let entry = NaptrEntry {
+        order: 50,
+        preference: 10,
+        flags: NaptrFlags::S,
+        services: NaptrServices::Other("E2U+sip".into()),
+        regexp: b"!^.*$!sip:info@example.com!".to_vec(),
+        replacement: Domain::from("_sip._udp.example.com"),
+    };
+
+    assert_eq!(
+        serde_json::to_value(&entry).unwrap(),
+        json!({
+            "order": 50,
+            "preference": 10,
+            "flags": "S",
+            "services": "E2U+sip",
+            "regexp": "!^.*$!sip:info@example.com!",
+            "replacement": "_sip._udp.example.com"
+        })
+    );
+    assert_round_trip(entry);
+}
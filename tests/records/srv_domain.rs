@@ -44,3 +44,51 @@ fn parses_srv_domain_correctly() {
     assert_eq!(srv_domain.transport(), rsip::Transport::TlsSctp);
     assert_eq!(srv_domain.to_string(), srv_domain_str.clone());
 }
+
+#[test]
+fn parses_generic_srv_domain_correctly() {
+    let srv_domain_str = "_stun._udp.example.com";
+    let srv_domain = SrvDomain::try_from(srv_domain_str).unwrap();
+    assert_eq!(srv_domain.service, SrvService::Stun);
+    assert!(!srv_domain.secure);
+    assert_eq!(srv_domain.protocol, rsip::Transport::Udp);
+    assert_eq!(srv_domain.domain, rsip::Domain::from("example.com"));
+    assert_eq!(srv_domain.to_string(), srv_domain_str);
+
+    let srv_domain_str = "_turns._tcp.example.com";
+    let srv_domain = SrvDomain::try_from(srv_domain_str).unwrap();
+    assert_eq!(srv_domain.service, SrvService::Turn);
+    assert!(srv_domain.secure);
+    assert_eq!(srv_domain.transport(), rsip::Transport::Tls);
+    assert_eq!(srv_domain.to_string(), srv_domain_str);
+
+    let srv_domain_str = "_sip+ws._tcp.example.com";
+    let srv_domain = SrvDomain::try_from(srv_domain_str).unwrap();
+    assert_eq!(srv_domain.service, SrvService::Other("sip+ws".into()));
+    assert_eq!(srv_domain.protocol, rsip::Transport::Tcp);
+    assert_eq!(srv_domain.to_string(), srv_domain_str);
+
+    let srv_domain =
+        SrvDomain::from((SrvService::Turn, "example.com".into(), rsip::Transport::Tls));
+    assert_eq!(srv_domain.to_string(), "_turns._tcp.example.com");
+    assert_eq!(SrvDomain::try_from(srv_domain.to_string().as_str()).unwrap(), srv_domain);
+
+    let srv_domain = SrvDomain::from((
+        SrvService::Other("sip+wss".into()),
+        "example.com".into(),
+        rsip::Transport::Tls,
+    ));
+    assert!(!srv_domain.secure);
+    assert_eq!(srv_domain.to_string(), "_sip+wss._tcp.example.com");
+    assert_eq!(SrvDomain::try_from(srv_domain.to_string().as_str()).unwrap(), srv_domain);
+
+    assert!(SrvDomain::try_from("_._udp.example.com").is_err());
+}
+
+#[test]
+fn random_srv_domains_round_trip() {
+    for _ in 0..100 {
+        let srv_domain = SrvDomain::random_with_any_service();
+        assert_eq!(SrvDomain::try_from(srv_domain.to_string().as_str()).unwrap(), srv_domain);
+    }
+}
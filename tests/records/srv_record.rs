@@ -0,0 +1,125 @@
+use rsip::Domain;
+use rsip_dns::{records::*, *};
+use std::{collections::HashMap, convert::TryFrom};
+
+fn srv_record_of(entries: &[(u16, u16, &str)]) -> SrvRecord {
+    SrvRecord {
+        domain: SrvDomain::try_from("_sip._udp.example.com").unwrap(),
+        entries: entries
+            .iter()
+            .map(|(priority, weight, target)| SrvEntry {
+                priority: *priority,
+                weight: *weight,
+                port: 5060.into(),
+                target: Domain::from(*target),
+            })
+            .collect(),
+        additional_addrs: vec![],
+    }
+}
+
+fn first_targets(srv_record: &SrvRecord, keys: usize) -> Vec<Domain> {
+    (0..keys)
+        .map(|key| {
+            srv_record.clone().rendezvous_sorted(&format!("call-{}", key)).targets()[0].clone()
+        })
+        .collect()
+}
+
+#[test]
+fn rendezvous_sorting_is_stable_per_key() {
+    let srv_record = srv_record_of(&[(10, 10, "a.example.com"), (10, 10, "b.example.com")]);
+
+    let sorted = srv_record.clone().rendezvous_sorted("call-1");
+    assert_eq!(sorted, srv_record.clone().rendezvous_sorted("call-1"));
+    assert_eq!(sorted.entries.len(), 2);
+
+    //the order of the answer doesn't matter
+    let mut reversed = srv_record.clone();
+    reversed.entries.reverse();
+    assert_eq!(reversed.rendezvous_sorted("call-1").targets(), sorted.targets());
+}
+
+#[test]
+fn rendezvous_sorting_respects_priority() {
+    let srv_record = srv_record_of(&[
+        (20, 100, "backup.example.com"),
+        (10, 1, "a.example.com"),
+        (10, 1, "b.example.com"),
+    ]);
+
+    for key in 0..100 {
+        let sorted = srv_record.clone().rendezvous_sorted(&key.to_string());
+        assert_eq!(sorted.targets()[2], Domain::from("backup.example.com"));
+    }
+}
+
+#[test]
+fn rendezvous_sorting_spreads_keys_by_weight() {
+    let srv_record = srv_record_of(&[
+        (10, 10, "a.example.com"),
+        (10, 30, "b.example.com"),
+        (10, 0, "c.example.com"),
+    ]);
+
+    let mut counts: HashMap<Domain, usize> = HashMap::new();
+    for target in first_targets(&srv_record, 4000) {
+        *counts.entry(target).or_default() += 1;
+    }
+
+    let a = counts.get(&Domain::from("a.example.com")).copied().unwrap_or_default();
+    let b = counts.get(&Domain::from("b.example.com")).copied().unwrap_or_default();
+    let c = counts.get(&Domain::from("c.example.com")).copied().unwrap_or_default();
+    assert!((800..1200).contains(&a), "a got {}", a);
+    assert!((2800..3200).contains(&b), "b got {}", b);
+    assert!(c < 20, "c got {}", c);
+}
+
+#[test]
+fn rendezvous_sorting_moves_few_keys_on_membership_changes() {
+    let srv_record = srv_record_of(&[
+        (10, 10, "a.example.com"),
+        (10, 10, "b.example.com"),
+        (10, 10, "c.example.com"),
+    ]);
+    let shrunk = srv_record_of(&[(10, 10, "a.example.com"), (10, 10, "b.example.com")]);
+
+    let before = first_targets(&srv_record, 1000);
+    let after = first_targets(&shrunk, 1000);
+
+    for (before, after) in before.iter().zip(after.iter()) {
+        if before != &Domain::from("c.example.com") {
+            assert_eq!(before, after);
+        }
+    }
+}
+
+#[tokio::test]
+async fn lookup_uses_rendezvous_selection() {
+    let zone = r#"
+$ORIGIN example.com.
+_sip._udp  SRV 10 10 5060 sip1
+           SRV 10 10 5060 sip2
+           SRV 10 10 5060 sip3
+sip1       A 10.0.0.1
+sip2       A 10.0.0.2
+sip3       A 10.0.0.3
+"#;
+    let dns_client = ZoneFileDnsClient::parse(zone).unwrap();
+    let srv_record = dns_client.srv_records()[0].clone();
+
+    for key in ["call-1", "call-2", "call-3"] {
+        let context = Context::initialize_from(
+            rsip::Uri::try_from("sip:example.com;transport=udp").unwrap(),
+            dns_client.clone(),
+            SupportedTransports::any(),
+        )
+        .unwrap()
+        .with_srv_selection(SrvSelection::Rendezvous(key.into()));
+
+        let target = Lookup::from(context).resolve_next().await.unwrap();
+        let expected = srv_record.clone().rendezvous_sorted(key).targets()[0].clone();
+        let expected = dns_client.ip_lookup(expected).await.unwrap().ip_addrs[0];
+        assert_eq!(target.ip_addr, expected);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::support::MockedDnsClient;
 use rsip::{Domain, Port, Transport};
-use rsip_dns::{records::*, resolvables::*, Target};
+use rsip_dns::{records::*, resolvables::*, ProvenanceStep, Target};
 
 #[tokio::test]
 async fn resolves_correctly() {
@@ -19,14 +19,19 @@ async fn resolves_correctly() {
     let port = Port::random();
     let transport = Transport::random();
 
-    let mut resolvable = ResolvableAddrRecord::new(dns_client.clone(), domain, port, transport);
+    let mut resolvable =
+        ResolvableAddrRecord::new(dns_client.clone(), domain.clone(), port, transport);
 
     assert_eq!(
         resolvable.resolve_next().await,
         dns_client.a_record.clone().unwrap().ip_addrs.first().map(|ip_addr| Target {
             ip_addr: ip_addr.clone(),
             port,
-            transport
+            transport,
+            server_name: transport.is_secure().then(|| domain.clone()),
+            srv_target: None,
+            provenance: vec![ProvenanceStep::Addr { domain: domain.clone(), ip_addr: *ip_addr }]
+                .into()
         })
     );
     assert_eq!(
@@ -34,7 +39,11 @@ async fn resolves_correctly() {
         dns_client.a_record.clone().unwrap().ip_addrs.last().map(|ip_addr| Target {
             ip_addr: ip_addr.clone(),
             port,
-            transport
+            transport,
+            server_name: transport.is_secure().then(|| domain.clone()),
+            srv_target: None,
+            provenance: vec![ProvenanceStep::Addr { domain: domain.clone(), ip_addr: *ip_addr }]
+                .into()
         })
     );
     assert!(resolvable.resolve_next().await.is_none());
@@ -81,6 +81,7 @@ static NAPTR_RECORD: Lazy<NaptrRecord> = Lazy::new(|| {
             replacement: "_sips._tcp.example.com.".into(),
         }],
         domain: DOMAIN.clone(),
+        additional_srvs: vec![],
     }
 });
 
@@ -103,6 +104,7 @@ static SRV_RECORD: Lazy<SrvRecord> = Lazy::new(|| {
             },
         ],
         domain: NAPTR_RECORD.entries.first().unwrap().clone().try_into().unwrap(),
+        additional_addrs: vec![],
     }
 });
 
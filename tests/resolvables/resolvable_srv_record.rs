@@ -45,6 +45,30 @@ async fn resolves_correctly() {
     assert!(resolvable.resolve_next().await.is_none());
 }
 
+#[tokio::test]
+async fn resolves_additional_addrs_without_queries() {
+    use rsip_dns::PanicDnsClient;
+
+    let mut srv_record = SRV_RECORD.clone();
+    srv_record.additional_addrs = TARGETS
+        .iter()
+        .map(|target| AddrRecord {
+            domain: target.clone(),
+            ip_addrs: IP_ADDRS.get(&target.to_string()).unwrap().clone(),
+        })
+        .collect();
+
+    let mut resolvable = ResolvableSrvRecord::new(PanicDnsClient, SRV_RECORD.domain.clone())
+        .with_preresolved(srv_record);
+
+    for target in TARGETS.iter() {
+        for ip_addr in IP_ADDRS.get(&target.to_string()).unwrap() {
+            assert_eq!(resolvable.resolve_next().await.map(|t| t.ip_addr), Some(*ip_addr));
+        }
+    }
+    assert!(resolvable.resolve_next().await.is_none());
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct CustomMockedDnsClient;
 
@@ -80,6 +104,7 @@ static SRV_RECORD: Lazy<SrvRecord> = Lazy::new(|| {
             },
         ],
         domain: Randomize::random(),
+        additional_addrs: vec![],
     }
 });
 
@@ -0,0 +1,196 @@
+use rsip_dns::{
+    records::*,
+    trust_dns_proto::rr::{record_data::RData, resource::Record, Name},
+    *,
+};
+use serde_json::Value;
+use std::{convert::TryFrom, process::Command};
+
+fn rsip_dns(server: &TestDnsServer, args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rsip-dns"))
+        .args(["--nameserver", &server.addr().to_string(), "--family", "ipv4"])
+        .args(args)
+        .output()
+        .unwrap();
+
+    (output.status.code().unwrap(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn prints_targets_with_queries() {
+    let server = TestDnsServer::start(zone()).unwrap();
+
+    let (code, stdout) = rsip_dns(&server, &["sips:alice@example.com"]);
+
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout,
+        "targets for sips:alice@example.com:\n\
+         1. 10.0.0.1:5061 TLS (server name example.com)\n   \
+            via NAPTR example.com SIPS+D2T -> SRV _sips._tcp.example.com. priority 10 weight 10 \
+            tls.example.com.:5061 -> A tls.example.com. 10.0.0.1\n   \
+            ? NAPTR example.com\n   \
+            ! NAPTR example.com: 10 10 SIPS+D2T _sips._tcp.example.com., \
+            20 10 SIP+D2U _sip._udp.example.com.\n   \
+            ? SRV _sips._tcp.example.com.\n   \
+            ! SRV _sips._tcp.example.com.: 10 10 tls.example.com.:5061\n   \
+            ? A/AAAA tls.example.com.\n   \
+            ! A/AAAA tls.example.com.: 10.0.0.1\n\
+         2. 10.0.0.1:5061 TLS (server name example.com)\n   \
+            via SRV _sips._tcp.example.com priority 10 weight 10 tls.example.com.:5061 -> \
+            A tls.example.com. 10.0.0.1\n   \
+            ? SRV _sips._tcp.example.com\n   \
+            ! SRV _sips._tcp.example.com: 10 10 tls.example.com.:5061\n   \
+            ? A/AAAA tls.example.com.\n   \
+            ! A/AAAA tls.example.com.: 10.0.0.1\n"
+    );
+}
+
+#[test]
+fn prints_targets_as_json() {
+    let server = TestDnsServer::start(zone()).unwrap();
+
+    let (code, stdout) = rsip_dns(&server, &["--json", "--transports", "udp", "sip:example.com"]);
+    let output: Value = serde_json::from_str(&stdout).unwrap();
+
+    assert_eq!(code, 0);
+    assert_eq!(output["uri"], "sip:example.com");
+    //the NAPTR branch, followed by the plain SRV branch
+    let targets = output["targets"].as_array().unwrap();
+    assert_eq!(targets.len(), 2);
+    assert_eq!(
+        serde_json::from_value::<Target>(targets[0]["target"].clone()).unwrap().socket_addr(),
+        "10.0.0.2:5060".parse().unwrap()
+    );
+    assert_eq!(targets[0]["target"]["transport"], "UDP");
+    assert_eq!(
+        targets[0]["dns"][0],
+        serde_json::json!({ "query": "NAPTR", "name": "example.com" })
+    );
+}
+
+#[test]
+fn fails_without_targets() {
+    let server = TestDnsServer::start(TestZone::new()).unwrap();
+
+    let (code, stdout) = rsip_dns(&server, &["sip:example.org"]);
+
+    assert_eq!(code, 1);
+    assert_eq!(stdout, "no targets found for sip:example.org\n");
+}
+
+#[test]
+fn rejects_invalid_arguments() {
+    let server = TestDnsServer::start(TestZone::new()).unwrap();
+
+    assert_eq!(rsip_dns(&server, &["--transports", "foo", "sip:example.com"]).0, 2);
+    assert_eq!(rsip_dns(&server, &["--family", "ipv5", "sip:example.com"]).0, 2);
+    assert_eq!(rsip_dns(&server, &[]).0, 2);
+}
+
+#[test]
+fn lints_domain() {
+    let alias = Name::from_ascii("alias.example.com.").unwrap();
+    let zone = zone()
+        .with_srv(SrvRecord {
+            domain: SrvDomain::try_from("_sip._tcp.example.org").unwrap(),
+            entries: vec![
+                SrvEntry {
+                    priority: 10,
+                    weight: 10,
+                    port: 5060.into(),
+                    target: "alias.example.com".into(),
+                },
+                SrvEntry {
+                    priority: 20,
+                    weight: 10,
+                    port: 5060.into(),
+                    target: "missing.example.com".into(),
+                },
+            ],
+            additional_addrs: vec![],
+        })
+        .with_record(Record::from_rdata(
+            alias,
+            300,
+            RData::CNAME(Name::from_ascii("tls.example.com.").unwrap()),
+        ));
+    let server = TestDnsServer::start(zone).unwrap();
+
+    let (code, stdout) = rsip_dns(&server, &["lint", "example.com"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "no problems found for example.com\n");
+
+    let (code, stdout) = rsip_dns(&server, &["lint", "--transports", "tcp", "example.org"]);
+    assert_eq!(code, 1);
+    //the test server doesn't follow aliases, hence the alias has no addresses either
+    assert_eq!(
+        stdout,
+        "problems found for example.org:\n\
+         warning: SRV _sip._tcp.example.org target alias.example.com. is an alias of \
+         tls.example.com. (CNAME), SRV targets must be canonical names\n\
+         error: SRV _sip._tcp.example.org target alias.example.com. has no A/AAAA records\n\
+         error: SRV _sip._tcp.example.org target missing.example.com. has no A/AAAA records\n"
+    );
+
+    let (_, stdout) = rsip_dns(&server, &["lint", "--json", "--transports", "tcp", "example.org"]);
+    let output: Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(output["domain"], "example.org");
+    assert_eq!(output["problems"][0]["severity"], "warning");
+    assert_eq!(output["problems"][2]["severity"], "error");
+}
+
+fn zone() -> TestZone {
+    TestZone::new()
+        .without_additionals()
+        .with_naptr(NaptrRecord {
+            domain: "example.com".into(),
+            entries: vec![
+                NaptrEntry {
+                    order: 10,
+                    preference: 10,
+                    flags: NaptrFlags::S,
+                    services: NaptrServices::SipsD2t,
+                    regexp: vec![],
+                    replacement: "_sips._tcp.example.com".into(),
+                },
+                NaptrEntry {
+                    order: 20,
+                    preference: 10,
+                    flags: NaptrFlags::S,
+                    services: NaptrServices::SipD2u,
+                    regexp: vec![],
+                    replacement: "_sip._udp.example.com".into(),
+                },
+            ],
+            additional_srvs: vec![],
+        })
+        .with_srv(SrvRecord {
+            domain: SrvDomain::try_from("_sips._tcp.example.com").unwrap(),
+            entries: vec![SrvEntry {
+                priority: 10,
+                weight: 10,
+                port: 5061.into(),
+                target: "tls.example.com".into(),
+            }],
+            additional_addrs: vec![],
+        })
+        .with_srv(SrvRecord {
+            domain: SrvDomain::try_from("_sip._udp.example.com").unwrap(),
+            entries: vec![SrvEntry {
+                priority: 10,
+                weight: 10,
+                port: 5060.into(),
+                target: "udp.example.com".into(),
+            }],
+            additional_addrs: vec![],
+        })
+        .with_addr(AddrRecord {
+            domain: "tls.example.com".into(),
+            ip_addrs: vec!["10.0.0.1".parse().unwrap()],
+        })
+        .with_addr(AddrRecord {
+            domain: "udp.example.com".into(),
+            ip_addrs: vec!["10.0.0.2".parse().unwrap()],
+        })
+}
@@ -0,0 +1,182 @@
+use rsip::Transport::*;
+use rsip_dns::{records::*, trust_dns_resolver::config::ResolverOpts, *};
+use std::convert::TryFrom;
+use trust_dns_resolver::{proto::op::ResponseCode, TokioAsyncResolver};
+
+#[tokio::test]
+async fn lookup_over_dns_with_additionals() {
+    let server = TestDnsServer::start(zone()).unwrap();
+
+    let mut lookup = Lookup::from(context_for(&server));
+
+    assert_eq!(targets_of(&mut lookup).await, expected_targets());
+
+    //the trust-dns resolver keeps the addresses of the SRV targets from the additional section,
+    //but drops the SRV records that come along with a NAPTR answer
+    let trace = lookup.trace();
+    assert!(trace.iter().any(|event| matches!(event, TraceEvent::AddrPreresolved(_))));
+    assert!(trace.iter().any(|event| matches!(event, TraceEvent::SrvQuery(_))));
+}
+
+#[tokio::test]
+async fn lookup_over_dns_without_additionals() {
+    let server = TestDnsServer::start(zone().without_additionals()).unwrap();
+
+    let mut lookup = Lookup::from(context_for(&server));
+
+    assert_eq!(targets_of(&mut lookup).await, expected_targets());
+
+    let trace = lookup.trace();
+    assert!(trace.iter().any(|event| matches!(event, TraceEvent::AddrQuery(_))));
+    assert!(!trace.iter().any(|event| matches!(event, TraceEvent::AddrPreresolved(_))));
+}
+
+#[tokio::test]
+async fn lookup_over_dns_with_unknown_domain() {
+    let server = TestDnsServer::start(TestZone::new()).unwrap();
+
+    let mut lookup = Lookup::from(context_for(&server));
+
+    assert_eq!(lookup.resolve_next().await, None);
+}
+
+//too many addresses to fit in a UDP answer, the resolver has to retry over TCP
+#[tokio::test]
+async fn lookup_over_dns_with_truncated_answer() {
+    let ip_addrs = (1..=64).map(|i| format!("10.0.1.{}", i).parse().unwrap()).collect::<Vec<_>>();
+    let zone = TestZone::new()
+        .with_addr(AddrRecord { domain: "example.com".into(), ip_addrs: ip_addrs.clone() });
+    let server = TestDnsServer::start(zone).unwrap();
+
+    let mut lookup = Lookup::from(context_for(&server).with_port(5060.into()));
+
+    let targets = targets_of(&mut lookup).await;
+    assert_eq!(targets.len(), ip_addrs.len());
+}
+
+fn context_for(server: &TestDnsServer) -> Context<impl DnsClient> {
+    let resolver =
+        TokioAsyncResolver::tokio(server.resolver_config(), ResolverOpts::default()).unwrap();
+
+    Context::new("example.com".into(), AsyncTrustDnsClient::new(resolver))
+}
+
+async fn targets_of<C: DnsClient>(lookup: &mut Lookup<C>) -> Vec<(String, u16, rsip::Transport)> {
+    let mut targets = vec![];
+    while let Some(target) = lookup.resolve_next().await {
+        targets.push((target.ip_addr.to_string(), target.port.into(), target.transport));
+    }
+
+    targets
+}
+
+//the NAPTR branch, followed by the plain SRV branches and the A fallback
+fn expected_targets() -> Vec<(String, u16, rsip::Transport)> {
+    vec![
+        ("10.0.0.1".into(), 5060, Tcp),
+        ("10.0.0.2".into(), 5060, Tcp),
+        ("10.0.0.3".into(), 5070, Udp),
+        ("10.0.0.3".into(), 5070, Udp),
+        ("10.0.0.1".into(), 5060, Tcp),
+        ("10.0.0.2".into(), 5060, Tcp),
+        ("10.0.0.4".into(), 5060, Udp),
+    ]
+}
+
+fn zone() -> TestZone {
+    TestZone::new()
+        .with_naptr(NaptrRecord {
+            domain: "example.com".into(),
+            entries: vec![
+                NaptrEntry {
+                    order: 10,
+                    preference: 10,
+                    flags: NaptrFlags::S,
+                    services: NaptrServices::SipD2t,
+                    regexp: vec![],
+                    replacement: "_sip._tcp.example.com".into(),
+                },
+                NaptrEntry {
+                    order: 20,
+                    preference: 10,
+                    flags: NaptrFlags::S,
+                    services: NaptrServices::SipD2u,
+                    regexp: vec![],
+                    replacement: "_sip._udp.example.com".into(),
+                },
+            ],
+            additional_srvs: vec![],
+        })
+        .with_srv(SrvRecord {
+            domain: SrvDomain::try_from("_sip._tcp.example.com").unwrap(),
+            entries: vec![SrvEntry {
+                priority: 10,
+                weight: 10,
+                port: 5060.into(),
+                target: "tcp.example.com".into(),
+            }],
+            additional_addrs: vec![],
+        })
+        .with_srv(SrvRecord {
+            domain: SrvDomain::try_from("_sip._udp.example.com").unwrap(),
+            entries: vec![SrvEntry {
+                priority: 10,
+                weight: 10,
+                port: 5070.into(),
+                target: "udp.example.com".into(),
+            }],
+            additional_addrs: vec![],
+        })
+        .with_addr(AddrRecord {
+            domain: "tcp.example.com".into(),
+            ip_addrs: vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()],
+        })
+        .with_addr(AddrRecord {
+            domain: "udp.example.com".into(),
+            ip_addrs: vec!["10.0.0.3".parse().unwrap()],
+        })
+        .with_addr(AddrRecord {
+            domain: "example.com".into(),
+            ip_addrs: vec!["10.0.0.4".parse().unwrap()],
+        })
+}
+
+#[tokio::test]
+async fn tells_apart_empty_answers_from_failures() {
+    let server = TestDnsServer::start(zone()).unwrap();
+    let dns_client = context_for(&server).dns_client;
+
+    assert!(matches!(dns_client.try_naptr_lookup("example.org".into()).await, Ok(None)));
+    assert!(matches!(dns_client.try_ip_lookup("example.org".into()).await, Ok(None)));
+    assert!(matches!(dns_client.try_naptr_lookup("example.com".into()).await, Ok(Some(_))));
+
+    let config = server.resolver_config();
+    drop(server);
+    let opts = ResolverOpts {
+        timeout: std::time::Duration::from_millis(100),
+        attempts: 1,
+        ..Default::default()
+    };
+    let dns_client = AsyncTrustDnsClient::new(TokioAsyncResolver::tokio(config, opts).unwrap());
+
+    assert!(dns_client.try_naptr_lookup("example.com".into()).await.is_err());
+}
+
+#[tokio::test]
+async fn fails_over_on_servfail() {
+    let failing = TestDnsServer::start(
+        zone().with_response_code("example.com".into(), ResponseCode::ServFail),
+    )
+    .unwrap();
+    let server = TestDnsServer::start(zone()).unwrap();
+    let failing_client = context_for(&failing).dns_client;
+
+    assert!(failing_client.try_naptr_lookup("example.com".into()).await.is_err());
+    assert!(failing_client.naptr_lookup("example.com".into()).await.is_none());
+
+    let dns_client = FallbackDnsClient::new(vec![failing_client, context_for(&server).dns_client]);
+    let record = dns_client.try_naptr_lookup("example.com".into()).await.unwrap().unwrap();
+
+    assert!(!record.entries.is_empty());
+    assert_eq!(dns_client.health(), vec![false, true]);
+}
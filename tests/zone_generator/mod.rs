@@ -0,0 +1,148 @@
+use rsip::Transport;
+use rsip_dns::{records::*, *};
+use std::convert::TryFrom;
+
+fn zone_generator() -> ZoneGenerator {
+    ZoneGenerator::new("example.com".into())
+        .with_node(
+            Node::new("sip1.example.com".into())
+                .with_listener(Transport::Tls, 5061.into())
+                .with_listener(Transport::Udp, 5060.into()),
+        )
+        .with_node(
+            Node::new("sip2.example.com".into())
+                .with_listener(Transport::Udp, 5070.into())
+                .with_listener(Transport::Tls, 5071.into())
+                .with_priority(20)
+                .with_weight(5),
+        )
+}
+
+#[test]
+fn generates_naptr_and_srv_records() {
+    let zone = zone_generator().generate().unwrap();
+
+    assert_eq!(
+        zone.naptr_record,
+        NaptrRecord {
+            domain: "example.com".into(),
+            entries: vec![
+                NaptrEntry {
+                    order: 10,
+                    preference: 10,
+                    flags: NaptrFlags::S,
+                    services: NaptrServices::SipsD2t,
+                    regexp: vec![],
+                    replacement: "_sips._tcp.example.com".into()
+                },
+                NaptrEntry {
+                    order: 20,
+                    preference: 10,
+                    flags: NaptrFlags::S,
+                    services: NaptrServices::SipD2u,
+                    regexp: vec![],
+                    replacement: "_sip._udp.example.com".into()
+                },
+            ],
+            additional_srvs: vec![],
+        }
+    );
+    assert_eq!(
+        zone.srv_records,
+        vec![
+            SrvRecord {
+                domain: SrvDomain::try_from("_sips._tcp.example.com").unwrap(),
+                entries: vec![
+                    SrvEntry {
+                        priority: 10,
+                        weight: 10,
+                        port: 5061.into(),
+                        target: "sip1.example.com".into()
+                    },
+                    SrvEntry {
+                        priority: 20,
+                        weight: 5,
+                        port: 5071.into(),
+                        target: "sip2.example.com".into()
+                    },
+                ],
+                additional_addrs: vec![],
+            },
+            SrvRecord {
+                domain: SrvDomain::try_from("_sip._udp.example.com").unwrap(),
+                entries: vec![
+                    SrvEntry {
+                        priority: 10,
+                        weight: 10,
+                        port: 5060.into(),
+                        target: "sip1.example.com".into()
+                    },
+                    SrvEntry {
+                        priority: 20,
+                        weight: 5,
+                        port: 5070.into(),
+                        target: "sip2.example.com".into()
+                    },
+                ],
+                additional_addrs: vec![],
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn renders_a_zone_file_that_resolves() {
+    let zone = zone_generator().with_ttl(300).generate().unwrap();
+    let rendered = zone.to_string();
+
+    assert_eq!(
+        rendered,
+        r#"example.com. 300 IN NAPTR 10 10 "S" "SIPS+D2T" "" _sips._tcp.example.com.
+example.com. 300 IN NAPTR 20 10 "S" "SIP+D2U" "" _sip._udp.example.com.
+_sips._tcp.example.com. 300 IN SRV 10 10 5061 sip1.example.com.
+_sips._tcp.example.com. 300 IN SRV 20 5 5071 sip2.example.com.
+_sip._udp.example.com. 300 IN SRV 10 10 5060 sip1.example.com.
+_sip._udp.example.com. 300 IN SRV 20 5 5070 sip2.example.com.
+"#
+    );
+
+    let dns_client = ZoneFileDnsClient::parse(&format!(
+        "{}sip1.example.com. IN A 10.0.0.1\nsip2.example.com. IN A 10.0.0.2\n",
+        rendered
+    ))
+    .unwrap();
+
+    assert_eq!(dns_client.naptr_lookup("example.com".into()).await, Some(zone.naptr_record));
+    for srv_record in zone.srv_records {
+        assert_eq!(dns_client.srv_lookup(srv_record.domain.clone()).await, Some(srv_record));
+    }
+    assert_eq!(Linter::new(dns_client).lint("example.com".into()).await.problems, vec![]);
+}
+
+#[test]
+fn naptr_services_match_transports() {
+    for transport in [
+        Transport::Udp,
+        Transport::Tcp,
+        Transport::Sctp,
+        Transport::Ws,
+        Transport::Tls,
+        Transport::Wss,
+    ] {
+        assert_eq!(NaptrServices::try_from(transport).unwrap().transport(), Some(transport));
+    }
+
+    let zone_generator = ZoneGenerator::new("example.com".into()).with_node(
+        Node::new("sip1.example.com".into()).with_listener(Transport::TlsSctp, 5061.into()),
+    );
+    assert!(zone_generator.generate().is_err());
+}
+
+#[test]
+fn generates_nothing_without_nodes() {
+    let zone = ZoneGenerator::new("example.com.".into()).generate().unwrap();
+
+    assert_eq!(zone.naptr_record.entries, vec![]);
+    assert_eq!(zone.srv_records, vec![]);
+    assert_eq!(zone.to_string(), "");
+}